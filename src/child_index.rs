@@ -0,0 +1,124 @@
+/// A child adjacency index built from a parent vector.
+///
+/// The index is stored in compressed-sparse-row form: `offsets[i]..offsets[i + 1]`
+/// is the range of `children` that holds the children of node `i`. Children
+/// are listed in ascending index order, i.e. in insertion order. Nodes whose
+/// parent index is out of range (such as the `usize::MAX` root convention)
+/// are collected in `roots`.
+///
+/// ```rust
+/// use apter::ChildIndex;
+/// let index = ChildIndex::new(&[usize::MAX, 0, 0, 1]);
+/// assert_eq!(index.children(0), &[1, 2]);
+/// assert_eq!(index.children(1), &[3]);
+/// assert!(index.children(3).is_empty());
+/// assert_eq!(index.roots(), &[0]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChildIndex {
+    offsets: Vec<usize>,
+    children: Vec<usize>,
+    roots: Vec<usize>,
+}
+
+impl ChildIndex {
+    /// Builds a child index from the given parent vector in O(n).
+    pub fn new(p: &[usize]) -> Self {
+        let len = p.len();
+        let mut offsets = vec![0; len + 1];
+        let mut roots = vec![];
+        for (idx, &parent) in p.iter().enumerate() {
+            if parent < len {
+                offsets[parent + 1] += 1;
+            } else {
+                roots.push(idx);
+            }
+        }
+        for i in 0..len {
+            offsets[i + 1] += offsets[i];
+        }
+
+        let mut next = offsets.clone();
+        let mut children = vec![0; offsets[len]];
+        for (idx, &parent) in p.iter().enumerate() {
+            if parent < len {
+                children[next[parent]] = idx;
+                next[parent] += 1;
+            }
+        }
+
+        Self {
+            offsets,
+            children,
+            roots,
+        }
+    }
+
+    /// Returns the number of nodes covered by the index.
+    pub fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// Returns `true` if the index covers no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the children of the given node, or an empty slice if the
+    /// index is out of range.
+    pub fn children(&self, idx: usize) -> &[usize] {
+        if idx < self.len() {
+            &self.children[self.offsets[idx]..self.offsets[idx + 1]]
+        } else {
+            &[]
+        }
+    }
+
+    /// Returns all nodes whose parent index is out of range.
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    /// Returns the given node and all of its descendants in preorder. Each
+    /// node is visited at most once, so this terminates even if `p` contains
+    /// a cycle.
+    pub fn preorder(&self, root: usize) -> Vec<usize> {
+        let mut order = vec![];
+        if root < self.len() {
+            self.preorder_into(root, &mut vec![false; self.len()], &mut order);
+        }
+        order
+    }
+
+    /// Returns every node ordered so that each parent comes before its
+    /// children: the preorder of each root in turn. Nodes that cannot be
+    /// reached from a root because `p` contains a cycle are appended at the
+    /// end.
+    pub fn parents_first(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.len()];
+        let mut order = Vec::with_capacity(self.len());
+        for &root in &self.roots {
+            self.preorder_into(root, &mut visited, &mut order);
+        }
+        if order.len() < self.len() {
+            for idx in 0..self.len() {
+                if !visited[idx] {
+                    self.preorder_into(idx, &mut visited, &mut order);
+                }
+            }
+        }
+        order
+    }
+
+    fn preorder_into(&self, root: usize, visited: &mut [bool], order: &mut Vec<usize>) {
+        let mut stack = vec![root];
+        while let Some(idx) = stack.pop() {
+            if visited[idx] {
+                continue;
+            }
+            visited[idx] = true;
+            order.push(idx);
+            stack.extend(self.children(idx).iter().rev());
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::iter;
+
+use crate::ApterTree;
+
+impl<T> ApterTree<T> {
+    /// Returns the depth of every node as a column indexed like `d` and `p`.
+    /// Roots have a depth of 0.
+    ///
+    /// This runs in O(n) by memoizing depths along `p`, regardless of the
+    /// order in which nodes were inserted.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// tree.insert("b", root);
+    /// tree.insert("a1", a);
+    /// assert_eq!(tree.depths(), vec![0, 1, 1, 2]);
+    /// assert_eq!(tree.height(), 3);
+    /// ```
+    pub fn depths(&self) -> Vec<usize> {
+        const UNKNOWN: usize = usize::MAX;
+
+        let len = self.len();
+        let mut depths = vec![UNKNOWN; len];
+        let mut path = vec![];
+        for start in 0..len {
+            // walk up until we reach a root or a node with a known depth; the
+            // length limit only matters if `p` contains a cycle
+            let mut idx = start;
+            while idx < len && depths[idx] == UNKNOWN && path.len() < len {
+                path.push(idx);
+                idx = self.p[idx];
+            }
+
+            let base = match depths.get(idx) {
+                Some(&known) if known != UNKNOWN => known + 1,
+                _ => 0,
+            };
+            for (offset, &idx) in path.iter().rev().enumerate() {
+                depths[idx] = base + offset;
+            }
+            path.clear();
+        }
+        depths
+    }
+
+    /// Returns the number of levels in the tree: 0 for an empty tree, 1 for a
+    /// tree consisting only of roots, and so on. This runs in O(n).
+    pub fn height(&self) -> usize {
+        self.depths().into_iter().max().map_or(0, |depth| depth + 1)
+    }
+
+    /// Aggregates every subtree bottom-up and returns the results as a column
+    /// indexed like `d` and `p`. Each node starts out as `init(value)`, and
+    /// then every child's aggregate is folded into its parent with
+    /// `combine(parent_acc, child_acc)`, children before parents. Children's
+    /// aggregates are passed by reference, since they are also part of the
+    /// result, so they are never copied.
+    ///
+    /// This runs in a single O(n) pass over `p`, regardless of the order in
+    /// which nodes were inserted.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// // disk usage per directory
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root(0);
+    /// let docs = tree.insert(4, root);
+    /// tree.insert(10, docs);
+    /// tree.insert(20, docs);
+    /// tree.insert(1, root);
+    /// assert_eq!(tree.reduce_up(|&size| size, |a, b| *a += b), vec![35, 34, 10, 20, 1]);
+    /// assert_eq!(tree.subtree_sizes(), vec![5, 3, 1, 1, 1]);
+    ///
+    /// // aggregates that are expensive to clone work just as well
+    /// let sizes = tree.map(|&size| vec![size]);
+    /// let mut all = sizes.reduce_up(|v| v.clone(), |a, b| a.extend(b));
+    /// all[docs.index()].sort();
+    /// assert_eq!(all[docs.index()], vec![4, 10, 20]);
+    /// ```
+    pub fn reduce_up<A>(
+        &self,
+        init: impl FnMut(&T) -> A,
+        mut combine: impl FnMut(&mut A, &A),
+    ) -> Vec<A> {
+        let mut acc: Vec<A> = self.d.iter().map(init).collect();
+        for idx in self.index_children().parents_first().into_iter().rev() {
+            let parent = self.p[idx];
+            // a node that is its own parent has nothing to fold
+            if parent < self.len() && parent != idx {
+                // borrow the parent mutably and the finished child immutably
+                let (parent_acc, child_acc) = if parent < idx {
+                    let (head, tail) = acc.split_at_mut(idx);
+                    (&mut head[parent], &tail[0])
+                } else {
+                    let (head, tail) = acc.split_at_mut(parent);
+                    (&mut tail[0], &head[idx])
+                };
+                combine(parent_acc, child_acc);
+            }
+        }
+        acc
+    }
+
+    /// Returns the number of nodes in every node's subtree, including the
+    /// node itself. This runs in O(n).
+    pub fn subtree_sizes(&self) -> Vec<usize> {
+        self.reduce_up(|_| 1, |size, child_size| *size += *child_size)
+    }
+
+    /// Accumulates a value from the roots down to every node and returns the
+    /// results as a column indexed like `d` and `p`. Each node's value is
+    /// `f(parent_acc, value)`, where `parent_acc` is the parent's accumulated
+    /// value, or `root_value` for roots.
+    ///
+    /// This runs in O(n), regardless of the order in which nodes were
+    /// inserted.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// // cumulative path costs
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root(1);
+    /// let a = tree.insert(2, root);
+    /// tree.insert(3, a);
+    /// tree.insert(4, root);
+    /// assert_eq!(tree.scan_down(0, |acc, &cost| acc + cost), vec![1, 3, 6, 5]);
+    /// ```
+    pub fn scan_down<A>(&self, root_value: A, mut f: impl FnMut(&A, &T) -> A) -> Vec<A> {
+        let mut acc: Vec<Option<A>> = iter::repeat_with(|| None).take(self.len()).collect();
+        for idx in self.index_children().parents_first() {
+            let parent_acc = acc.get(self.p[idx]).and_then(Option::as_ref);
+            let v = f(parent_acc.unwrap_or(&root_value), &self.d[idx]);
+            acc[idx] = Some(v);
+        }
+        acc.into_iter().flatten().collect()
+    }
+}
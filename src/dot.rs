@@ -0,0 +1,241 @@
+//! Exporting trees to the Graphviz DOT format.
+//!
+//! [`to_dot`] covers the common case of labelling each node. For more
+//! control, [`Dot`] also supports edge attributes, clustering the trees of a
+//! forest, and highlighting a set of nodes such as the path to a root.
+//!
+//! ```rust
+//! use apter::{dot, ApterTree};
+//! let mut tree = ApterTree::new();
+//! let root = tree.insert_root("root");
+//! let a = tree.insert("a", root);
+//! tree.insert("b", root);
+//!
+//! let path = std::iter::once(a).chain(tree.ancestors(a));
+//! let output = dot::Dot::new(&tree, |_, &v| dot::NodeAttrs::with_label(v))
+//!     .highlight(path)
+//!     .to_string();
+//! assert_eq!(
+//!     output,
+//!     r#"digraph {
+//!     n0 [label="root", color="red", penwidth="2"];
+//!     n1 [label="a", color="red", penwidth="2"];
+//!     n2 [label="b"];
+//!     n0 -> n1 [color="red", penwidth="2"];
+//!     n0 -> n2;
+//! }
+//! "#,
+//! );
+//! ```
+
+use std::fmt;
+
+use crate::{ApterTree, NodeId};
+
+/// Graphviz attributes for a node. Unset attributes are left to Graphviz's
+/// defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeAttrs {
+    pub label: Option<String>,
+    pub color: Option<String>,
+    pub fillcolor: Option<String>,
+    pub shape: Option<String>,
+    /// Any other attributes, as `(name, value)` pairs.
+    pub extra: Vec<(String, String)>,
+}
+
+impl NodeAttrs {
+    /// Creates attributes with just a label.
+    pub fn with_label(label: impl fmt::Display) -> Self {
+        Self {
+            label: Some(label.to_string()),
+            ..Self::default()
+        }
+    }
+}
+
+/// Graphviz attributes for an edge. Unset attributes are left to Graphviz's
+/// defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EdgeAttrs {
+    pub label: Option<String>,
+    pub color: Option<String>,
+    pub style: Option<String>,
+    /// Any other attributes, as `(name, value)` pairs.
+    pub extra: Vec<(String, String)>,
+}
+
+/// Renders a tree as a DOT digraph, with one edge from each parent to each
+/// of its children. `node_attrs` is called once per node.
+pub fn to_dot<T>(tree: &ApterTree<T>, node_attrs: impl Fn(NodeId, &T) -> NodeAttrs) -> String {
+    Dot::new(tree, node_attrs).to_string()
+}
+
+type NodeAttrsFn<'a, T> = dyn Fn(NodeId, &T) -> NodeAttrs + 'a;
+type EdgeAttrsFn<'a> = dyn Fn(NodeId, NodeId) -> EdgeAttrs + 'a;
+
+/// A configurable DOT rendering of a tree. Use its `Display` implementation
+/// to produce the output.
+pub struct Dot<'a, T> {
+    tree: &'a ApterTree<T>,
+    node_attrs: Box<NodeAttrsFn<'a, T>>,
+    edge_attrs: Option<Box<EdgeAttrsFn<'a>>>,
+    cluster_roots: bool,
+    highlighted: Vec<bool>,
+    highlight_color: String,
+}
+
+impl<'a, T> Dot<'a, T> {
+    /// Creates a rendering of `tree`, calling `node_attrs` once per node.
+    pub fn new(tree: &'a ApterTree<T>, node_attrs: impl Fn(NodeId, &T) -> NodeAttrs + 'a) -> Self {
+        Self {
+            tree,
+            node_attrs: Box::new(node_attrs),
+            edge_attrs: None,
+            cluster_roots: false,
+            highlighted: vec![false; tree.len()],
+            highlight_color: "red".to_string(),
+        }
+    }
+
+    /// Sets the attributes of each edge, given the parent and the child.
+    pub fn edge_attrs(mut self, edge_attrs: impl Fn(NodeId, NodeId) -> EdgeAttrs + 'a) -> Self {
+        self.edge_attrs = Some(Box::new(edge_attrs));
+        self
+    }
+
+    /// Places the nodes of each tree of a forest in their own cluster
+    /// subgraph, so that Graphviz draws a box around each tree.
+    ///
+    /// ```rust
+    /// use apter::{dot::Dot, ApterTree};
+    /// let mut tree = ApterTree::new();
+    /// tree.insert_root("a");
+    /// tree.insert_root("b");
+    /// let output = Dot::new(&tree, |_, _| Default::default())
+    ///     .cluster_roots(true)
+    ///     .to_string();
+    /// assert!(output.contains("subgraph cluster_0 {\n        n0;\n    }"));
+    /// assert!(output.contains("subgraph cluster_1 {\n        n1;\n    }"));
+    /// ```
+    pub fn cluster_roots(mut self, cluster_roots: bool) -> Self {
+        self.cluster_roots = cluster_roots;
+        self
+    }
+
+    /// Highlights the given nodes, along with the edges between them, in the
+    /// highlight color. Ids that are out of range are ignored.
+    pub fn highlight(mut self, ids: impl IntoIterator<Item = NodeId>) -> Self {
+        for id in ids {
+            if let Some(flag) = self.highlighted.get_mut(id.index()) {
+                *flag = true;
+            }
+        }
+        self
+    }
+
+    /// Sets the color used for highlighting, `"red"` by default.
+    pub fn highlight_color(mut self, color: impl Into<String>) -> Self {
+        self.highlight_color = color.into();
+        self
+    }
+
+    fn write_node(&self, f: &mut fmt::Formatter<'_>, idx: usize, indent: &str) -> fmt::Result {
+        let attrs = (self.node_attrs)(NodeId::new(idx), &self.tree.d[idx]);
+        let mut pairs = vec![];
+        push_attr(&mut pairs, "label", &attrs.label);
+        if self.highlighted[idx] {
+            pairs.push(("color", &self.highlight_color));
+            pairs.push(("penwidth", "2"));
+        } else {
+            push_attr(&mut pairs, "color", &attrs.color);
+        }
+        push_attr(&mut pairs, "fillcolor", &attrs.fillcolor);
+        push_attr(&mut pairs, "shape", &attrs.shape);
+        pairs.extend(attrs.extra.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+
+        write!(f, "{indent}n{idx}")?;
+        write_attrs(f, &pairs)?;
+        writeln!(f, ";")
+    }
+
+    fn write_edge(&self, f: &mut fmt::Formatter<'_>, parent: usize, child: usize) -> fmt::Result {
+        let attrs = match &self.edge_attrs {
+            Some(edge_attrs) => edge_attrs(NodeId::new(parent), NodeId::new(child)),
+            None => EdgeAttrs::default(),
+        };
+        let mut pairs = vec![];
+        push_attr(&mut pairs, "label", &attrs.label);
+        if self.highlighted[parent] && self.highlighted[child] {
+            pairs.push(("color", &self.highlight_color));
+            pairs.push(("penwidth", "2"));
+        } else {
+            push_attr(&mut pairs, "color", &attrs.color);
+        }
+        push_attr(&mut pairs, "style", &attrs.style);
+        pairs.extend(attrs.extra.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+
+        write!(f, "    n{parent} -> n{child}")?;
+        write_attrs(f, &pairs)?;
+        writeln!(f, ";")
+    }
+}
+
+impl<T> fmt::Display for Dot<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph {{")?;
+
+        if self.cluster_roots {
+            let index = self.tree.index_children();
+            let mut written = vec![false; self.tree.len()];
+            for &root in index.roots() {
+                writeln!(f, "    subgraph cluster_{root} {{")?;
+                for idx in index.preorder(root) {
+                    written[idx] = true;
+                    self.write_node(f, idx, "        ")?;
+                }
+                writeln!(f, "    }}")?;
+            }
+            // nodes on a cycle belong to no root's tree
+            for idx in (0..self.tree.len()).filter(|&idx| !written[idx]) {
+                self.write_node(f, idx, "    ")?;
+            }
+        } else {
+            for idx in 0..self.tree.len() {
+                self.write_node(f, idx, "    ")?;
+            }
+        }
+
+        for (child, &parent) in self.tree.p.iter().enumerate() {
+            if parent < self.tree.len() {
+                self.write_edge(f, parent, child)?;
+            }
+        }
+
+        writeln!(f, "}}")
+    }
+}
+
+fn push_attr<'a>(pairs: &mut Vec<(&'a str, &'a str)>, name: &'a str, value: &'a Option<String>) {
+    if let Some(value) = value {
+        pairs.push((name, value));
+    }
+}
+
+fn write_attrs(f: &mut fmt::Formatter<'_>, pairs: &[(&str, &str)]) -> fmt::Result {
+    if pairs.is_empty() {
+        return Ok(());
+    }
+    write!(f, " [")?;
+    for (i, (name, value)) in pairs.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        let value = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        write!(f, "{name}=\"{value}\"")?;
+    }
+    write!(f, "]")
+}
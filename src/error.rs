@@ -0,0 +1,60 @@
+use std::{error, fmt};
+
+use crate::NodeId;
+
+/// The error type for fallible [`ApterTree`](crate::ApterTree) operations
+/// and structural validation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ApterError {
+    /// A node id is out of range for a tree with `len` nodes.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// The operation would leave `child` without its parent `node`.
+    HasChildren { node: NodeId, child: NodeId },
+    /// `d` and `p` have different lengths.
+    LengthMismatch { values: usize, parents: usize },
+    /// A node has a parent index that is out of range, other than the
+    /// `usize::MAX` root marker.
+    InvalidParent { node: NodeId, parent: usize },
+    /// A node is its own parent.
+    SelfParent { node: NodeId },
+    /// Following parent indices from these nodes leads back around in a
+    /// cycle. The nodes are listed in child-to-parent order.
+    Cycle { nodes: Vec<NodeId> },
+    /// The tree has more than one root, but a single root was required.
+    MultipleRoots { roots: Vec<NodeId> },
+}
+
+impl fmt::Display for ApterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IndexOutOfBounds { index, len } => {
+                write!(
+                    f,
+                    "node index {index} is out of range for a tree of {len} nodes"
+                )
+            }
+            Self::HasChildren { node, child } => {
+                write!(f, "node {node} still has child {child}")
+            }
+            Self::LengthMismatch { values, parents } => {
+                write!(f, "tree has {values} values but {parents} parent indices")
+            }
+            Self::InvalidParent { node, parent } => {
+                write!(f, "node {node} has out-of-range parent index {parent}")
+            }
+            Self::SelfParent { node } => write!(f, "node {node} is its own parent"),
+            Self::Cycle { nodes } => {
+                write!(f, "parent indices form a cycle:")?;
+                for node in nodes {
+                    write!(f, " {node}")?;
+                }
+                Ok(())
+            }
+            Self::MultipleRoots { roots } => {
+                write!(f, "expected a single root, found {}", roots.len())
+            }
+        }
+    }
+}
+
+impl error::Error for ApterError {}
@@ -0,0 +1,131 @@
+use std::mem;
+
+use crate::{ApterTree, NodeId};
+
+impl<T> ApterTree<T> {
+    /// Returns an iterator through all root nodes, i.e. the nodes whose
+    /// parent index is `usize::MAX`. A tree holding several independent
+    /// trees is called a forest.
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.keys().filter(|&id| self.is_root(id))
+    }
+
+    /// Returns the root of the tree that the given node belongs to, which is
+    /// the node itself if it is a root. This is O(depth).
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut forest = ApterTree::new();
+    /// let a = forest.insert_root("a");
+    /// let b = forest.insert_root("b");
+    /// let b1 = forest.insert("b1", b);
+    /// let b2 = forest.insert("b2", b1);
+    /// assert_eq!(forest.tree_of(a), a);
+    /// assert_eq!(forest.tree_of(b2), b);
+    /// assert_eq!(forest.roots().collect::<Vec<_>>(), vec![a, b]);
+    /// ```
+    pub fn tree_of(&self, id: NodeId) -> NodeId {
+        self.ancestors(id).last().unwrap_or(id)
+    }
+
+    /// Splits a forest into one tree per root, ordered by the index of their
+    /// roots. Nodes keep their relative order within each tree. This runs in
+    /// O(n).
+    ///
+    /// Nodes on a cycle, which belong to no root, are split off into trees
+    /// of their own, with the cycle broken by making one of them a root.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut forest = ApterTree::new();
+    /// let a = forest.insert_root("a");
+    /// let b = forest.insert_root("b");
+    /// forest.insert("b1", b);
+    /// forest.insert("a1", a);
+    ///
+    /// let trees = forest.split_forest();
+    /// assert_eq!(trees[0].d, vec!["a", "a1"]);
+    /// assert_eq!(trees[1].d, vec!["b", "b1"]);
+    /// assert_eq!(trees[1].p, vec![usize::MAX, 0]);
+    ///
+    /// let joined = ApterTree::join(trees);
+    /// assert_eq!(joined.d, vec!["a", "a1", "b", "b1"]);
+    /// assert_eq!(joined.p, vec![usize::MAX, 0, usize::MAX, 2]);
+    ///
+    /// let mut cycle = ApterTree::new();
+    /// cycle.d = vec!["x", "y", "z"];
+    /// cycle.p = vec![1, 2, 0];
+    /// for tree in cycle.split_forest() {
+    ///     assert_eq!(tree.validate_rooted(), Ok(()));
+    ///     assert_eq!(tree.roots().count(), 1);
+    /// }
+    /// ```
+    pub fn split_forest(mut self) -> Vec<ApterTree<T>> {
+        let len = self.len();
+
+        // `label[idx]` is the root of the tree containing `idx`; nodes that
+        // only lead to a cycle are treated as roots of their own trees
+        let mut label = vec![usize::MAX; len];
+        for idx in self.index_children().parents_first() {
+            let parent = self.p[idx];
+            label[idx] = match label.get(parent) {
+                Some(&root) if root != usize::MAX => root,
+                _ => idx,
+            };
+        }
+
+        let mut tree_of_root = vec![usize::MAX; len];
+        let mut trees = vec![];
+        for idx in 0..len {
+            if label[idx] == idx {
+                tree_of_root[idx] = trees.len();
+                trees.push(ApterTree::new());
+            }
+        }
+
+        // the index of each node within its own tree
+        let mut counts = vec![0; trees.len()];
+        let local: Vec<usize> = (0..len)
+            .map(|idx| {
+                let count = &mut counts[tree_of_root[label[idx]]];
+                *count += 1;
+                *count - 1
+            })
+            .collect();
+
+        for (idx, v) in mem::take(&mut self.d).into_iter().enumerate() {
+            let parent = self.p[idx];
+            let tree = &mut trees[tree_of_root[label[idx]]];
+            tree.d.push(v);
+            let is_root = label[idx] == idx;
+            tree.p
+                .push(if !is_root && parent < len && label[parent] == label[idx] {
+                    local[parent]
+                } else {
+                    usize::MAX
+                });
+        }
+
+        trees
+    }
+
+    /// Concatenates several trees into one forest, offsetting their parent
+    /// indices. The nodes of each tree keep their order, and each tree's
+    /// roots remain roots.
+    pub fn join(trees: impl IntoIterator<Item = ApterTree<T>>) -> Self {
+        let mut forest = Self::new();
+        for tree in trees {
+            let offset = forest.len();
+            let len = tree.len();
+            forest.d.extend(tree.d);
+            forest.p.extend(tree.p.into_iter().map(|parent| {
+                if parent < len {
+                    parent + offset
+                } else {
+                    usize::MAX
+                }
+            }));
+        }
+        forest
+    }
+}
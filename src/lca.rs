@@ -0,0 +1,113 @@
+use crate::{ApterTree, NodeId};
+
+/// A lowest common ancestor index over a static tree, using binary lifting.
+///
+/// Building the index takes O(n log h) time and memory, where h is the height
+/// of the tree. Afterwards, [`lca`](Self::lca) and
+/// [`distance`](Self::distance) queries take O(log h). The index is a
+/// snapshot: it does not reflect later changes to the tree.
+///
+/// ```rust
+/// use apter::{ApterTree, LcaIndex};
+/// let mut tree = ApterTree::new();
+/// let root = tree.insert_root("root");
+/// let a = tree.insert("a", root);
+/// let b = tree.insert("b", root);
+/// let a1 = tree.insert("a1", a);
+/// let a2 = tree.insert("a2", a);
+///
+/// let index = LcaIndex::new(&tree);
+/// assert_eq!(index.lca(a1, a2), Some(a));
+/// assert_eq!(index.lca(a1, b), Some(root));
+/// assert_eq!(index.lca(a1, a), Some(a));
+/// assert_eq!(index.distance(a1, b), Some(3));
+/// ```
+#[derive(Clone, Debug)]
+pub struct LcaIndex {
+    depths: Vec<usize>,
+    // `up[k][idx]` is the 2^k-th ancestor of `idx`, or the topmost ancestor
+    // of `idx` if there is none
+    up: Vec<Vec<usize>>,
+}
+
+impl LcaIndex {
+    /// Builds an index over the given tree.
+    pub fn new<T>(tree: &ApterTree<T>) -> Self {
+        let len = tree.len();
+        let depths = tree.depths();
+        let height = depths.iter().max().map_or(0, |&depth| depth + 1);
+
+        let parents = tree
+            .p
+            .iter()
+            .enumerate()
+            .map(|(idx, &parent)| if parent < len { parent } else { idx })
+            .collect();
+        let mut up: Vec<Vec<usize>> = vec![parents];
+        while 1 << up.len() < height {
+            let prev = &up[up.len() - 1];
+            let next = prev.iter().map(|&ancestor| prev[ancestor]).collect();
+            up.push(next);
+        }
+
+        Self { depths, up }
+    }
+
+    /// Returns the depth of the given node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.depths[id.index()]
+    }
+
+    /// Returns the lowest common ancestor of `a` and `b`, which may be `a` or
+    /// `b` itself. Returns `None` if the nodes belong to different trees of a
+    /// forest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either id is out of range.
+    pub fn lca(&self, a: NodeId, b: NodeId) -> Option<NodeId> {
+        let (mut a, mut b) = (a.index(), b.index());
+        if self.depths[a] < self.depths[b] {
+            (a, b) = (b, a);
+        }
+
+        a = self.ancestor_at(a, self.depths[a] - self.depths[b]);
+        if a == b {
+            return Some(NodeId::new(a));
+        }
+
+        for level in self.up.iter().rev() {
+            if level[a] != level[b] {
+                a = level[a];
+                b = level[b];
+            }
+        }
+
+        let parents = &self.up[0];
+        (parents[a] == parents[b] && parents[a] != a).then_some(NodeId::new(parents[a]))
+    }
+
+    /// Returns the number of edges on the path between `a` and `b`, or
+    /// `None` if the nodes belong to different trees of a forest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either id is out of range.
+    pub fn distance(&self, a: NodeId, b: NodeId) -> Option<usize> {
+        let lca = self.lca(a, b)?;
+        Some(self.depth(a) + self.depth(b) - 2 * self.depth(lca))
+    }
+
+    fn ancestor_at(&self, mut idx: usize, steps: usize) -> usize {
+        for (k, level) in self.up.iter().enumerate() {
+            if steps & (1 << k) != 0 {
+                idx = level[idx];
+            }
+        }
+        idx
+    }
+}
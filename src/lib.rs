@@ -1,19 +1,56 @@
-use std::{iter, ops};
+use std::{borrow::Cow, fmt, iter, mem, ops, sync::OnceLock};
+
+mod child_index;
+mod columns;
+pub mod dot;
+mod error;
+mod forest;
+mod lca;
+mod map;
+mod nested;
+pub mod newick;
+mod node_id;
+mod preorder_index;
+mod pretty;
+mod reparent;
+mod retain;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod siblings;
+mod subtree;
+mod traversal;
+mod validate;
+
+pub use child_index::ChildIndex;
+pub use error::ApterError;
+pub use lca::LcaIndex;
+pub use nested::{NestedChildren, NestedNode};
+pub use node_id::NodeId;
+pub use preorder_index::PreorderIndex;
+pub use pretty::Pretty;
+pub use retain::OrphanPolicy;
+pub use traversal::{Bfs, Levels, Postorder, PostorderMut, Preorder, PreorderMut};
 
 /// ApterTree is a tree data structure that stores elements of type `T`.
 ///
+/// Nodes are addressed by [`NodeId`] handles. The raw columns are available
+/// as `d` (node values) and `p` (parent indices, with `usize::MAX` marking a
+/// root) for vectorized code.
+///
 /// ```rust
 /// use apter::ApterTree;
 /// let mut tree = ApterTree::new();
-/// tree.insert("root", usize::MAX);
-/// tree.insert("a", 0);
-/// tree.insert("b", 0);
+/// let root = tree.insert_root("root");
+/// tree.insert("a", root);
+/// tree.insert("b", root);
 /// assert_eq!(tree.len(), 3);
+/// assert_eq!(tree.p, vec![usize::MAX, 0, 0]);
 /// ```
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct ApterTree<T> {
     pub d: Vec<T>,
     pub p: Vec<usize>,
+    child_index: Option<OnceLock<ChildIndex>>,
 }
 
 impl<T> ApterTree<T> {
@@ -22,6 +59,7 @@ impl<T> ApterTree<T> {
         Self {
             d: vec![],
             p: vec![],
+            child_index: None,
         }
     }
 
@@ -35,92 +73,423 @@ impl<T> ApterTree<T> {
         self.p.is_empty()
     }
 
-    /// Returns an iterator over all item indices in the tree.
-    pub fn keys(&self) -> ops::Range<usize> {
-        0..self.len()
+    /// Returns an iterator over all node ids in the tree.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = NodeId> + ExactSizeIterator {
+        (0..self.len()).map(NodeId::new)
+    }
+
+    /// Insert a new item into the tree as a child of the given parent, and
+    /// return its id.
+    pub fn insert(&mut self, v: T, parent: NodeId) -> NodeId {
+        self.push(v, parent.index())
+    }
+
+    /// Like [`insert`](Self::insert), but returns an error instead of
+    /// inserting if `parent` is out of range.
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, ApterError, NodeId};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// assert_eq!(tree.try_insert("a", root), Ok(NodeId::new(1)));
+    /// assert_eq!(
+    ///     tree.try_insert("b", NodeId::new(5)),
+    ///     Err(ApterError::InvalidParent { node: NodeId::new(2), parent: 5 }),
+    /// );
+    /// assert_eq!(tree.len(), 2);
+    /// ```
+    pub fn try_insert(&mut self, v: T, parent: NodeId) -> Result<NodeId, ApterError> {
+        if parent.index() >= self.len() {
+            return Err(ApterError::InvalidParent {
+                node: NodeId::new(self.len()),
+                parent: parent.index(),
+            });
+        }
+        Ok(self.insert(v, parent))
+    }
+
+    /// Insert a new root item into the tree, and return its id. Root nodes
+    /// are stored with a parent index of `usize::MAX` in `p`.
+    pub fn insert_root(&mut self, v: T) -> NodeId {
+        self.push(v, usize::MAX)
     }
 
-    /// Insert a new item into the tree, with the given parent index. By
-    /// convention, the root node has a parent index of `usize::MAX`.
-    pub fn insert(&mut self, v: T, parent_idx: usize) {
+    fn push(&mut self, v: T, parent_idx: usize) -> NodeId {
+        let id = NodeId::new(self.len());
         self.d.push(v);
         self.p.push(parent_idx);
+        self.invalidate_child_index();
+        id
+    }
+
+    /// Returns the parent of the given node, or `None` if it is a root.
+    pub fn parent_of(&self, child: NodeId) -> Option<NodeId> {
+        let parent_idx = self.p[child.index()];
+        (parent_idx < self.len()).then_some(NodeId::new(parent_idx))
+    }
+
+    /// Like [`parent_of`](Self::parent_of), but returns an error instead of
+    /// panicking if `child` is out of range.
+    ///
+    /// The other `try_` lookups behave the same way:
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, ApterError, NodeId};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// assert_eq!(tree.try_parent_of(a), Ok(Some(root)));
+    /// assert_eq!(tree.try_parent_of(root), Ok(None));
+    ///
+    /// let missing = NodeId::new(2);
+    /// let err = ApterError::IndexOutOfBounds { index: 2, len: 2 };
+    /// assert_eq!(tree.try_parent_of(missing), Err(err.clone()));
+    /// assert_eq!(tree.try_is_root(missing), Err(err.clone()));
+    /// assert_eq!(tree.try_get(missing), Err(err.clone()));
+    /// assert_eq!(tree.try_get_mut(missing), Err(err.clone()));
+    /// assert_eq!(tree.try_children(missing).err(), Some(err.clone()));
+    /// assert_eq!(tree.try_is_leaf(missing), Err(err.clone()));
+    /// assert_eq!(tree.try_ancestors(missing), Err(err.clone()));
+    /// assert_eq!(tree.try_delete(missing), Err(err.clone()));
+    /// assert_eq!(tree.try_delete_subtree(missing), Err(err));
+    ///
+    /// assert_eq!(tree.try_get(a), Ok(&"a"));
+    /// assert_eq!(tree.try_is_root(root), Ok(true));
+    /// assert_eq!(tree.try_is_root(a), Ok(false));
+    /// assert_eq!(tree.try_is_leaf(root), Ok(false));
+    /// assert_eq!(tree.try_children(root).unwrap().collect::<Vec<_>>(), vec![a]);
+    /// ```
+    pub fn try_parent_of(&self, child: NodeId) -> Result<Option<NodeId>, ApterError> {
+        self.check(child)?;
+        Ok(self.parent_of(child))
+    }
+
+    /// Returns `true` if the given node is a root node.
+    pub fn is_root(&self, id: NodeId) -> bool {
+        self.parent_of(id).is_none()
+    }
+
+    /// Like [`is_root`](Self::is_root), but returns an error instead of
+    /// panicking if `id` is out of range.
+    pub fn try_is_root(&self, id: NodeId) -> Result<bool, ApterError> {
+        self.check(id)?;
+        Ok(self.is_root(id))
+    }
+
+    /// Returns a reference to the item with the given id.
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        self.d.get(id.index())
     }
 
-    /// Returns the parent index of the given child index.
-    pub fn parent_of(&self, child_idx: usize) -> usize {
-        self.p[child_idx]
+    /// Like [`get`](Self::get), but returns an error if `id` is out of range.
+    pub fn try_get(&self, id: NodeId) -> Result<&T, ApterError> {
+        self.check(id)?;
+        Ok(&self.d[id.index()])
     }
 
-    /// Returns a reference to the item at the given index.
-    pub fn get(&self, idx: usize) -> Option<&T> {
-        self.d.get(idx)
+    /// Returns a mutable reference to the item with the given id.
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.d.get_mut(id.index())
     }
 
-    /// Returns a mutable reference to the item at the given index.
-    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
-        self.d.get_mut(idx)
+    /// Like [`get_mut`](Self::get_mut), but returns an error if `id` is out
+    /// of range.
+    pub fn try_get_mut(&mut self, id: NodeId) -> Result<&mut T, ApterError> {
+        self.check(id)?;
+        Ok(&mut self.d[id.index()])
     }
 
     /// Iterates through all items in the tree in insertion order.
-    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
-        self.keys().map(move |idx| (idx, &self.d[idx]))
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &T)> {
+        self.keys().zip(&self.d)
     }
 
-    /// Searches for an item in the tree and returns its index if found.
-    pub fn find(&self, v: &T) -> Option<usize>
+    /// Searches for an item in the tree and returns its id if found.
+    pub fn find(&self, v: &T) -> Option<NodeId>
     where
         T: PartialEq,
     {
-        self.d.iter().position(|x| x == v)
+        self.d.iter().position(|x| x == v).map(NodeId::new)
     }
 
-    /// Returns an iterator through all children of the given parent index.
-    pub fn children(&self, parent_idx: usize) -> impl Iterator<Item = usize> + '_ {
-        self.keys().filter(move |idx| self.p[*idx] == parent_idx)
+    /// Enables the cached child index. Once enabled, `children` and `is_leaf`
+    /// use a compressed-sparse-row index of `p` instead of scanning every
+    /// element, so `children(idx)` becomes O(children).
+    ///
+    /// The index is built lazily on first use and is discarded by `insert`
+    /// and `delete`. If you modify `p` directly, call
+    /// [`invalidate_child_index`](Self::invalidate_child_index) afterwards.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// tree.enable_child_index();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let b = tree.insert("b", root);
+    /// assert_eq!(tree.children(root).collect::<Vec<_>>(), vec![a, b]);
+    /// assert_eq!(tree.leaves().collect::<Vec<_>>(), vec![a, b]);
+    /// ```
+    pub fn enable_child_index(&mut self) {
+        if self.child_index.is_none() {
+            self.child_index = Some(OnceLock::new());
+        }
     }
 
-    /// Returns `true` if the item at `idx` is a leaf node.
-    pub fn is_leaf(&self, idx: usize) -> bool {
-        self.children(idx).next().is_none()
+    /// Disables the cached child index and frees its memory.
+    pub fn disable_child_index(&mut self) {
+        self.child_index = None;
     }
 
-    /// Returns an iterator through all leaf nodes in the tree.
-    pub fn leaves(&self) -> impl Iterator<Item = usize> + '_ {
-        self.keys()
-            .filter(move |idx| self.children(*idx).next().is_none())
+    /// Discards the cached child index, if any, so that it is rebuilt from
+    /// `p` on next use.
+    pub fn invalidate_child_index(&mut self) {
+        if let Some(cache) = &mut self.child_index {
+            cache.take();
+        }
     }
 
-    /// Returns an iterator through all ancestors of the given index.
-    pub fn ancestors(&self, idx: usize) -> impl Iterator<Item = usize> + '_ {
-        let parent = self.parent_of(idx);
-        let len = self.len();
-        iter::successors(Some(parent), |&idx| {
-            self.get(idx).map(|_| self.parent_of(idx))
-        })
-        .take_while(move |&idx| idx < len)
+    /// Returns the cached child index, building it if necessary. Returns
+    /// `None` if the child index has not been enabled.
+    pub fn cached_child_index(&self) -> Option<&ChildIndex> {
+        self.child_index
+            .as_ref()
+            .map(|cache| cache.get_or_init(|| ChildIndex::new(&self.p)))
+    }
+
+    /// Returns a child index for the tree, either the cached one or a newly
+    /// built one. Either way this is at most O(n).
+    pub fn index_children(&self) -> Cow<'_, ChildIndex> {
+        match self.cached_child_index() {
+            Some(index) => Cow::Borrowed(index),
+            None => Cow::Owned(ChildIndex::new(&self.p)),
+        }
+    }
+
+    /// Returns an iterator through all children of the given node, in
+    /// sibling order (i.e. index order). This scans every element unless the
+    /// child index is enabled.
+    pub fn children(&self, parent: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let parent_idx = parent.index();
+        let cached = self
+            .cached_child_index()
+            .map(|index| index.children(parent_idx).iter().copied());
+        let scan = cached
+            .is_none()
+            .then(|| (0..self.len()).filter(move |&idx| self.p[idx] == parent_idx));
+        cached
+            .into_iter()
+            .flatten()
+            .chain(scan.into_iter().flatten())
+            .map(NodeId::new)
+    }
+
+    /// Like [`children`](Self::children), but returns an error if `parent`
+    /// is out of range.
+    pub fn try_children(
+        &self,
+        parent: NodeId,
+    ) -> Result<impl Iterator<Item = NodeId> + '_, ApterError> {
+        self.check(parent)?;
+        Ok(self.children(parent))
+    }
+
+    /// Returns `true` if the given node is a leaf node.
+    pub fn is_leaf(&self, id: NodeId) -> bool {
+        self.children(id).next().is_none()
+    }
+
+    /// Like [`is_leaf`](Self::is_leaf), but returns an error if `id` is out
+    /// of range.
+    pub fn try_is_leaf(&self, id: NodeId) -> Result<bool, ApterError> {
+        self.check(id)?;
+        Ok(self.is_leaf(id))
+    }
+
+    /// Returns an iterator through all leaf nodes in the tree. This is an O(n)
+    /// operation.
+    pub fn leaves(&self) -> impl Iterator<Item = NodeId> + '_ {
+        let mut has_children = vec![false; self.len()];
+        for &parent in &self.p {
+            if let Some(flag) = has_children.get_mut(parent) {
+                *flag = true;
+            }
+        }
+        self.keys().filter(move |id| !has_children[id.index()])
+    }
+
+    /// Returns an iterator through all ancestors of the given node, starting
+    /// with its parent.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        iter::successors(self.parent_of(id), |&id| self.parent_of(id))
+    }
+
+    /// Returns all ancestors of the given node, starting with its parent.
+    /// Unlike [`ancestors`](Self::ancestors), this returns an error if `id`
+    /// is out of range, or if its ancestors form a cycle.
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, ApterError};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let b = tree.insert("b", a);
+    /// assert_eq!(tree.try_ancestors(b), Ok(vec![a, root]));
+    ///
+    /// tree.p[root.index()] = b.index();
+    /// assert_eq!(
+    ///     tree.try_ancestors(b),
+    ///     Err(ApterError::Cycle { nodes: vec![a, root, b] }),
+    /// );
+    /// ```
+    pub fn try_ancestors(&self, id: NodeId) -> Result<Vec<NodeId>, ApterError> {
+        self.check(id)?;
+        let mut ancestors = vec![];
+        for ancestor in self.ancestors(id) {
+            if ancestors.len() >= self.len() {
+                // a path longer than the tree must revisit a node
+                let start = ancestors.iter().position(|&a| a == ancestor).unwrap_or(0);
+                return Err(ApterError::Cycle {
+                    nodes: ancestors.split_off(start),
+                });
+            }
+            ancestors.push(ancestor);
+        }
+        Ok(ancestors)
     }
 
-    /// Delete the node at the given index. This is an O(n) operation since all
-    /// indices after the deleted node must be shifted down by one. The node
-    /// being deleted should not have any child elements, otherwise they will
-    /// point at the wrong parent index.
-    pub fn delete(&mut self, idx: usize) -> Option<T> {
+    /// Delete the given node. This is an O(n) operation since all indices
+    /// after the deleted node must be shifted down by one, which also
+    /// invalidates any `NodeId` after it. The node being deleted should not
+    /// have any child elements, otherwise they will point at the wrong parent
+    /// index.
+    pub fn delete(&mut self, id: NodeId) -> Option<T> {
+        let idx = id.index();
         if idx >= self.len() {
             return None;
         }
 
+        let len = self.len();
         let v = self.d.remove(idx);
         self.p.remove(idx);
+        self.invalidate_child_index();
 
-        for i in 0..self.len() {
-            if self.p[i] > idx {
-                self.p[i] -= 1;
+        // root markers and other out-of-range parents are left alone
+        for parent in &mut self.p {
+            if *parent > idx && *parent < len {
+                *parent -= 1;
             }
         }
 
         Some(v)
     }
+
+    /// Like [`delete`](Self::delete), but returns an error instead of
+    /// corrupting the tree if the node still has children.
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, ApterError};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// assert_eq!(
+    ///     tree.try_delete(root),
+    ///     Err(ApterError::HasChildren { node: root, child: a }),
+    /// );
+    /// assert_eq!(tree.len(), 2);
+    /// assert_eq!(tree.try_delete(a), Ok("a"));
+    /// assert_eq!(tree.p, vec![usize::MAX]);
+    /// ```
+    pub fn try_delete(&mut self, id: NodeId) -> Result<T, ApterError> {
+        self.check(id)?;
+        if let Some(child) = self.children(id).next() {
+            return Err(ApterError::HasChildren { node: id, child });
+        }
+        Ok(self.delete(id).expect("id was checked"))
+    }
+
+    /// Delete the given node along with all of its descendants, and return
+    /// the removed values in preorder. Unlike [`delete`](Self::delete), this
+    /// never leaves dangling parent indices behind. The remaining nodes are
+    /// compacted in a single O(n) pass. Use
+    /// [`take_subtree`](Self::take_subtree) instead to also get the new
+    /// indices of the remaining nodes.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// tree.insert("b", root);
+    /// tree.insert("a1", a);
+    /// assert_eq!(tree.delete_subtree(a), Some(vec!["a", "a1"]));
+    /// assert_eq!(tree.d, vec!["root", "b"]);
+    /// assert_eq!(tree.p, vec![usize::MAX, 0]);
+    /// ```
+    pub fn delete_subtree(&mut self, id: NodeId) -> Option<Vec<T>> {
+        self.take_subtree(id).map(|(tree, _, _)| tree.d)
+    }
+
+    /// Like [`delete_subtree`](Self::delete_subtree), but returns an error if
+    /// `id` is out of range.
+    pub fn try_delete_subtree(&mut self, id: NodeId) -> Result<Vec<T>, ApterError> {
+        self.check(id)?;
+        Ok(self.delete_subtree(id).expect("id was checked"))
+    }
+
+    /// Returns an error if `id` is out of range.
+    fn check(&self, id: NodeId) -> Result<(), ApterError> {
+        if id.index() < self.len() {
+            Ok(())
+        } else {
+            Err(ApterError::IndexOutOfBounds {
+                index: id.index(),
+                len: self.len(),
+            })
+        }
+    }
+
+    /// Removes every node flagged in `remove` in a single O(n) pass and
+    /// rewrites the remaining parent indices. Surviving nodes whose parent is
+    /// removed or out of range become roots. Returns the removed values in
+    /// index order, together with the old-to-new index mapping.
+    fn compact(&mut self, remove: &[bool]) -> (Vec<T>, Vec<Option<NodeId>>) {
+        let len = self.len();
+        let mut remap = Vec::with_capacity(len);
+        let mut next = 0;
+        for &r in remove {
+            if r {
+                remap.push(None);
+            } else {
+                remap.push(Some(NodeId::new(next)));
+                next += 1;
+            }
+        }
+
+        let mut kept = Vec::with_capacity(next);
+        let mut removed = Vec::with_capacity(len - next);
+        for (v, &r) in mem::take(&mut self.d).into_iter().zip(remove) {
+            if r {
+                removed.push(v);
+            } else {
+                kept.push(v);
+            }
+        }
+        self.d = kept;
+
+        let mut write = 0;
+        for (read, &r) in remove.iter().enumerate() {
+            if !r {
+                let parent = remap.get(self.p[read]).copied().flatten();
+                self.p[write] = parent.map_or(usize::MAX, NodeId::index);
+                write += 1;
+            }
+        }
+        self.p.truncate(write);
+        self.invalidate_child_index();
+
+        (removed, remap)
+    }
 }
 
 impl<T> Default for ApterTree<T> {
@@ -129,6 +498,15 @@ impl<T> Default for ApterTree<T> {
     }
 }
 
+impl<T: fmt::Debug> fmt::Debug for ApterTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApterTree")
+            .field("d", &self.d)
+            .field("p", &self.p)
+            .finish()
+    }
+}
+
 impl<T> ops::Index<usize> for ApterTree<T> {
     type Output = T;
 
@@ -142,3 +520,17 @@ impl<T> ops::IndexMut<usize> for ApterTree<T> {
         &mut self.d[idx]
     }
 }
+
+impl<T> ops::Index<NodeId> for ApterTree<T> {
+    type Output = T;
+
+    fn index(&self, id: NodeId) -> &Self::Output {
+        &self.d[id.index()]
+    }
+}
+
+impl<T> ops::IndexMut<NodeId> for ApterTree<T> {
+    fn index_mut(&mut self, id: NodeId) -> &mut Self::Output {
+        &mut self.d[id.index()]
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{ApterTree, NodeId};
+
+impl<T> ApterTree<T> {
+    /// Returns a new tree with the same shape, where every value has been
+    /// transformed by `f`.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("1");
+    /// tree.insert("22", root);
+    /// let lengths = tree.map(|v| v.len());
+    /// assert_eq!(lengths.d, vec![1, 2]);
+    /// assert_eq!(lengths.p, tree.p);
+    ///
+    /// let parsed = tree.try_map(|v| v.parse::<u32>()).unwrap();
+    /// assert_eq!(parsed.d, vec![1, 22]);
+    /// ```
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> ApterTree<U> {
+        self.with_values(self.d.iter().map(f).collect())
+    }
+
+    /// Like [`map`](Self::map), but also passes each node's id to `f`.
+    pub fn map_with_index<U>(&self, mut f: impl FnMut(NodeId, &T) -> U) -> ApterTree<U> {
+        self.with_values(self.iter().map(|(id, v)| f(id, v)).collect())
+    }
+
+    /// Like [`map`](Self::map), but stops at and returns the first error
+    /// produced by `f`.
+    pub fn try_map<U, E>(&self, f: impl FnMut(&T) -> Result<U, E>) -> Result<ApterTree<U>, E> {
+        Ok(self.with_values(self.d.iter().map(f).collect::<Result<_, _>>()?))
+    }
+
+    /// Consumes the tree and transforms every value with `f`, reusing the
+    /// allocation of `p` (and the child index, if enabled).
+    pub fn into_map<U>(self, f: impl FnMut(T) -> U) -> ApterTree<U> {
+        ApterTree {
+            d: self.d.into_iter().map(f).collect(),
+            p: self.p,
+            child_index: self.child_index,
+        }
+    }
+
+    fn with_values<U>(&self, d: Vec<U>) -> ApterTree<U> {
+        ApterTree {
+            d,
+            p: self.p.clone(),
+            child_index: self.child_index.clone(),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::{fmt, mem, ops, slice, vec};
+
+use crate::{ApterTree, NodeId};
+
+/// A node of an ordinary recursive tree, for interoperating with code that
+/// does not use Apter trees.
+///
+/// Conversions between `NestedNode` and [`ApterTree`] use explicit stacks
+/// rather than recursion, so they work on very deep trees, and so does
+/// dropping a `NestedNode` (see [`NestedChildren`]). Note that cloning,
+/// comparing or formatting one is still recursive, as it is for any
+/// recursive type.
+///
+/// ```rust
+/// use apter::{ApterTree, NestedNode};
+/// let nested = NestedNode::new("root")
+///     .with_child(NestedNode::new("a").with_child(NestedNode::new("a1")))
+///     .with_child(NestedNode::new("b"));
+///
+/// let tree = ApterTree::from(nested.clone());
+/// assert_eq!(tree.d, vec!["root", "a", "a1", "b"]);
+/// assert_eq!(tree.p, vec![usize::MAX, 0, 1, 0]);
+/// assert_eq!(tree.to_nested(tree.roots().next().unwrap()), Some(nested.clone()));
+///
+/// let NestedNode { value, children } = nested;
+/// assert_eq!(value, "root");
+/// assert_eq!(children.len(), 2);
+///
+/// let mut deep = NestedNode::new(0);
+/// for i in 1..1_000_000 {
+///     deep = NestedNode::new(i).with_child(deep);
+/// }
+/// let tree = ApterTree::from(deep);
+/// let deep = tree.to_nested(tree.roots().next().unwrap()).unwrap();
+/// drop(deep);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename = "Node"))]
+pub struct NestedNode<T> {
+    pub value: T,
+    #[cfg_attr(feature = "serde", serde(default = "NestedChildren::default"))]
+    pub children: NestedChildren<T>,
+}
+
+impl<T> NestedNode<T> {
+    /// Creates a node without children.
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            children: NestedChildren(vec![]),
+        }
+    }
+
+    /// Appends a child to this node and returns it.
+    pub fn with_child(mut self, child: NestedNode<T>) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+/// The children of a [`NestedNode`]. This dereferences to a
+/// `Vec<NestedNode<T>>`, and only exists so that dropping a deep tree
+/// releases its nodes one at a time instead of recursing, which would
+/// overflow the stack.
+///
+/// ```rust
+/// use apter::{NestedChildren, NestedNode};
+/// let mut children: NestedChildren<_> = vec![NestedNode::new(1)].into();
+/// children.push(NestedNode::new(2));
+/// let values: Vec<_> = children.into_iter().map(|child| child.value).collect();
+/// assert_eq!(values, vec![1, 2]);
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct NestedChildren<T>(Vec<NestedNode<T>>);
+
+impl<T> NestedChildren<T> {
+    /// Returns the children as a plain vector.
+    pub fn into_vec(mut self) -> Vec<NestedNode<T>> {
+        mem::take(&mut self.0)
+    }
+}
+
+impl<T> Drop for NestedChildren<T> {
+    fn drop(&mut self) {
+        let mut stack = mem::take(&mut self.0);
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.children.0);
+        }
+    }
+}
+
+impl<T> Default for NestedChildren<T> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for NestedChildren<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> ops::Deref for NestedChildren<T> {
+    type Target = Vec<NestedNode<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for NestedChildren<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<Vec<NestedNode<T>>> for NestedChildren<T> {
+    fn from(children: Vec<NestedNode<T>>) -> Self {
+        Self(children)
+    }
+}
+
+impl<T> From<NestedChildren<T>> for Vec<NestedNode<T>> {
+    fn from(children: NestedChildren<T>) -> Self {
+        children.into_vec()
+    }
+}
+
+impl<T> FromIterator<NestedNode<T>> for NestedChildren<T> {
+    fn from_iter<I: IntoIterator<Item = NestedNode<T>>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<T> IntoIterator for NestedChildren<T> {
+    type Item = NestedNode<T>;
+    type IntoIter = vec::IntoIter<NestedNode<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_vec().into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a NestedChildren<T> {
+    type Item = &'a NestedNode<T>;
+    type IntoIter = slice::Iter<'a, NestedNode<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut NestedChildren<T> {
+    type Item = &'a mut NestedNode<T>;
+    type IntoIter = slice::IterMut<'a, NestedNode<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
+
+/// Converts a nested tree into an Apter tree, storing the nodes in preorder.
+impl<T> From<NestedNode<T>> for ApterTree<T> {
+    fn from(root: NestedNode<T>) -> Self {
+        let mut tree = ApterTree::new();
+        let mut stack = vec![(root, usize::MAX)];
+        while let Some((NestedNode { value, children }, parent_idx)) = stack.pop() {
+            let idx = tree.len();
+            tree.d.push(value);
+            tree.p.push(parent_idx);
+            stack.extend(children.into_iter().rev().map(|child| (child, idx)));
+        }
+        tree
+    }
+}
+
+impl<T> ApterTree<T> {
+    /// Returns a copy of the subtree rooted at `root` as a nested tree, or
+    /// `None` if `root` is out of range.
+    pub fn to_nested(&self, root: NodeId) -> Option<NestedNode<T>>
+    where
+        T: Clone,
+    {
+        // in postorder, the children of each node are the nodes at the next
+        // depth that are on top of the stack by the time it is visited
+        let mut stack: Vec<(usize, NestedNode<T>)> = vec![];
+        for (_, v, depth) in self.postorder(root) {
+            let first_child = stack
+                .iter()
+                .rposition(|&(d, _)| d != depth + 1)
+                .map_or(0, |pos| pos + 1);
+            let children = stack.drain(first_child..).map(|(_, child)| child).collect();
+            stack.push((
+                depth,
+                NestedNode {
+                    value: v.clone(),
+                    children,
+                },
+            ));
+        }
+        stack.pop().map(|(_, node)| node)
+    }
+}
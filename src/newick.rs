@@ -0,0 +1,306 @@
+//! Reading and writing phylogenetic trees in the Newick format.
+//!
+//! Node labels (including quoted labels), branch lengths and bracketed
+//! comments are preserved, so trees survive a round trip through
+//! [`parse`] and [`write_newick`]. Several trees, each terminated by `;`,
+//! are parsed into a single forest with one root per tree. Both directions
+//! use explicit stacks rather than recursion, so very deep trees are fine.
+//!
+//! ```rust
+//! use apter::newick;
+//! let tree = newick::parse("(A:0.1,'B c':0.2[note],(D,E)F:0.5)root;").unwrap();
+//! assert_eq!(tree.len(), 6);
+//! assert_eq!(tree.d[2].label.as_deref(), Some("B c"));
+//! assert_eq!(tree.d[2].length, Some(0.2));
+//! assert_eq!(tree.d[2].comments, vec!["note"]);
+//! assert_eq!(
+//!     newick::write_newick(&tree),
+//!     "(A:0.1,'B c':0.2[note],(D,E)F:0.5)root;\n",
+//! );
+//! ```
+
+use std::{error, fmt, mem};
+
+use crate::ApterTree;
+
+/// A node of a Newick tree.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NewickNode {
+    /// The node's label, or `None` if it has none. Unquoted underscores are
+    /// read as spaces, as the format specifies.
+    pub label: Option<String>,
+    /// The length of the branch leading to this node. Lengths that are not
+    /// finite, such as `inf` or `NaN`, are rejected by [`parse`].
+    pub length: Option<f64>,
+    /// The contents of any bracketed comments attached to this node. The
+    /// format has no way to escape `]`, so a comment containing one cannot
+    /// be written by [`write_newick`] in a way that parses back the same.
+    pub comments: Vec<String>,
+}
+
+/// An error encountered while parsing a Newick string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    offset: usize,
+    message: &'static str,
+}
+
+impl ParseError {
+    /// Returns the byte offset in the input at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Parses one or more `;`-terminated Newick trees. Nodes are stored in
+/// preorder, and each tree becomes a root of the returned forest.
+///
+/// ```rust
+/// use apter::newick;
+/// let err = newick::parse("(A:1,B:inf);").unwrap_err();
+/// assert_eq!(err.to_string(), "invalid branch length at byte 7");
+/// assert!(newick::parse("A:NaN;").is_err());
+/// assert!(newick::parse("(A:1,B").is_err());
+/// ```
+pub fn parse(s: &str) -> Result<ApterTree<NewickNode>, ParseError> {
+    let mut parser = Parser {
+        s,
+        pos: 0,
+        pending: vec![],
+    };
+    let mut tree = ApterTree::new();
+    parser.skip_trivia()?;
+    if parser.peek().is_none() {
+        return Err(parser.error("expected a tree"));
+    }
+
+    while parser.peek().is_some() {
+        // internal nodes whose closing parenthesis has not been seen yet
+        let mut open: Vec<usize> = vec![];
+        loop {
+            // start of a subtree
+            parser.skip_trivia()?;
+            let parent = open.last().copied().unwrap_or(usize::MAX);
+            let idx = tree.len();
+            tree.d.push(NewickNode {
+                comments: mem::take(&mut parser.pending),
+                ..NewickNode::default()
+            });
+            tree.p.push(parent);
+            if parser.peek() == Some('(') {
+                parser.pos += 1;
+                open.push(idx);
+                continue;
+            }
+            parser.parse_suffix(&mut tree.d[idx])?;
+
+            // end of a subtree
+            loop {
+                match parser.peek() {
+                    Some(',') if !open.is_empty() => {
+                        parser.pos += 1;
+                        break;
+                    }
+                    Some(')') if !open.is_empty() => {
+                        parser.pos += 1;
+                        let idx = open.pop().expect("checked above");
+                        parser.parse_suffix(&mut tree.d[idx])?;
+                    }
+                    Some(';') if open.is_empty() => {
+                        parser.pos += 1;
+                        break;
+                    }
+                    _ if !open.is_empty() => return Err(parser.error("expected ',' or ')'")),
+                    _ => return Err(parser.error("expected ';'")),
+                }
+            }
+            if open.is_empty() {
+                break;
+            }
+        }
+
+        // comments after the ';' belong to the root of the tree just parsed
+        parser.skip_trivia()?;
+        let root = tree.p.iter().rposition(|&parent| parent == usize::MAX);
+        let root = &mut tree.d[root.expect("every tree has a root")];
+        root.comments.append(&mut parser.pending);
+    }
+
+    Ok(tree)
+}
+
+/// Writes a tree in Newick format, with each root of a forest as a separate
+/// `;`-terminated line. Labels are quoted when necessary. Comments are
+/// written as they are, so any comment containing `]` ends early and the
+/// output will not parse back into the same tree.
+pub fn write_newick(tree: &ApterTree<NewickNode>) -> String {
+    enum Step {
+        Enter(usize),
+        Comma,
+        Close(usize),
+    }
+
+    let index = tree.index_children();
+    let mut out = String::new();
+    for &root in index.roots() {
+        let mut stack = vec![Step::Enter(root)];
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Enter(idx) => {
+                    let children = index.children(idx);
+                    if children.is_empty() {
+                        write_suffix(&mut out, &tree.d[idx]);
+                    } else {
+                        out.push('(');
+                        stack.push(Step::Close(idx));
+                        for (i, &child) in children.iter().enumerate().rev() {
+                            stack.push(Step::Enter(child));
+                            if i > 0 {
+                                stack.push(Step::Comma);
+                            }
+                        }
+                    }
+                }
+                Step::Comma => out.push(','),
+                Step::Close(idx) => {
+                    out.push(')');
+                    write_suffix(&mut out, &tree.d[idx]);
+                }
+            }
+        }
+        out.push_str(";\n");
+    }
+    out
+}
+
+fn write_suffix(out: &mut String, node: &NewickNode) {
+    if let Some(label) = &node.label {
+        let needs_quotes = label.is_empty()
+            || label
+                .chars()
+                .any(|c| c.is_whitespace() || "()[]':;,_".contains(c));
+        if needs_quotes {
+            out.push('\'');
+            out.push_str(&label.replace('\'', "''"));
+            out.push('\'');
+        } else {
+            out.push_str(label);
+        }
+    }
+    if let Some(length) = node.length {
+        out.push(':');
+        out.push_str(&length.to_string());
+    }
+    for comment in &node.comments {
+        out.push('[');
+        out.push_str(comment);
+        out.push(']');
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+    // comments that have been read but not yet attached to a node
+    pending: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn error(&self, message: &'static str) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            message,
+        }
+    }
+
+    /// Skips whitespace, and collects any comments into `pending`.
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        while let Some(c) = self.peek() {
+            if c == '[' {
+                let start = self.pos + 1;
+                let len = self.s[start..]
+                    .find(']')
+                    .ok_or_else(|| self.error("unterminated comment"))?;
+                self.pending.push(self.s[start..start + len].to_string());
+                self.pos = start + len + 1;
+            } else if c.is_whitespace() {
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the label, branch length and comments that follow a leaf or
+    /// a closing parenthesis, and attaches them to `node`.
+    fn parse_suffix(&mut self, node: &mut NewickNode) -> Result<(), ParseError> {
+        self.skip_trivia()?;
+        node.label = self.parse_label()?;
+        self.skip_trivia()?;
+        if self.peek() == Some(':') {
+            self.pos += 1;
+            self.skip_trivia()?;
+            let start = self.pos;
+            let text = self.take_unquoted();
+            let length = text
+                .parse()
+                .ok()
+                .filter(|length: &f64| length.is_finite())
+                .ok_or(ParseError {
+                    offset: start,
+                    message: "invalid branch length",
+                })?;
+            node.length = Some(length);
+            self.skip_trivia()?;
+        }
+        node.comments.append(&mut self.pending);
+        Ok(())
+    }
+
+    fn parse_label(&mut self) -> Result<Option<String>, ParseError> {
+        if self.peek() != Some('\'') {
+            let text = self.take_unquoted();
+            return Ok((!text.is_empty()).then(|| text.replace('_', " ")));
+        }
+
+        let mut label = String::new();
+        self.pos += 1;
+        loop {
+            let rest = &self.s[self.pos..];
+            let end = rest
+                .find('\'')
+                .ok_or_else(|| self.error("unterminated quoted label"))?;
+            label.push_str(&rest[..end]);
+            self.pos += end + 1;
+            // a doubled quote stands for a literal quote
+            if self.peek() == Some('\'') {
+                label.push('\'');
+                self.pos += 1;
+            } else {
+                return Ok(Some(label));
+            }
+        }
+    }
+
+    fn take_unquoted(&mut self) -> &'a str {
+        let rest = &self.s[self.pos..];
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "()[]':;,".contains(c))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+}
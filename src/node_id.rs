@@ -0,0 +1,51 @@
+use std::fmt;
+
+/// A typed handle to a node in an [`ApterTree`](crate::ApterTree).
+///
+/// A `NodeId` is a thin wrapper around the node's position in the `d` and
+/// `p` columns. Use [`index`](Self::index) to get the raw index for
+/// vectorized code, and [`NodeId::new`] or `From<usize>` to go back.
+///
+/// ```rust
+/// use apter::{ApterTree, NodeId};
+/// let mut tree = ApterTree::new();
+/// let root = tree.insert_root("root");
+/// let a = tree.insert("a", root);
+/// assert_eq!(a, NodeId::new(1));
+/// assert_eq!(a.index(), 1);
+/// assert_eq!(tree.parent_of(a), Some(root));
+/// assert_eq!(tree.parent_of(root), None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Creates a handle from a raw index.
+    pub const fn new(idx: usize) -> Self {
+        Self(idx)
+    }
+
+    /// Returns the raw index of this node.
+    pub const fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl From<usize> for NodeId {
+    fn from(idx: usize) -> Self {
+        Self(idx)
+    }
+}
+
+impl From<NodeId> for usize {
+    fn from(id: NodeId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
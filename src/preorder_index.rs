@@ -0,0 +1,109 @@
+use std::ops;
+
+use crate::{ApterTree, NodeId};
+
+/// A nested-set labelling of a static tree, built from its preorder.
+///
+/// Every node is assigned an interval `enter..exit` of positions in the
+/// preorder permutation of the whole forest, covering the node and all of
+/// its descendants. This makes ancestor tests O(1), and lets the descendants
+/// of a node be read off as a contiguous slice. Building the index takes
+/// O(n). The index is a snapshot: it does not reflect later changes to the
+/// tree.
+///
+/// ```rust
+/// use apter::{ApterTree, PreorderIndex};
+/// let mut tree = ApterTree::new();
+/// let root = tree.insert_root("root");
+/// let a = tree.insert("a", root);
+/// let b = tree.insert("b", root);
+/// let a1 = tree.insert("a1", a);
+///
+/// let index = PreorderIndex::new(&tree);
+/// assert!(index.is_ancestor(root, a1));
+/// assert!(!index.is_ancestor(b, a1));
+/// assert_eq!(index.interval(a), 1..3);
+/// assert_eq!(index.descendants(root), &[a, a1, b]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct PreorderIndex {
+    order: Vec<NodeId>,
+    enter: Vec<usize>,
+    exit: Vec<usize>,
+}
+
+impl PreorderIndex {
+    /// Builds an index over the given tree.
+    pub fn new<T>(tree: &ApterTree<T>) -> Self {
+        let len = tree.len();
+        let order = tree.index_children().parents_first();
+
+        let mut enter = vec![0; len];
+        for (pos, &idx) in order.iter().enumerate() {
+            enter[idx] = pos;
+        }
+
+        let mut sizes = vec![1; len];
+        for &idx in order.iter().rev() {
+            let parent = tree.p[idx];
+            if parent < len {
+                sizes[parent] += sizes[idx];
+            }
+        }
+        let exit = enter.iter().zip(sizes).map(|(e, s)| e + s).collect();
+
+        Self {
+            order: order.into_iter().map(NodeId::new).collect(),
+            enter,
+            exit,
+        }
+    }
+
+    /// Returns the preorder permutation of the whole forest, i.e. the
+    /// preorder of each root in turn.
+    pub fn order(&self) -> &[NodeId] {
+        &self.order
+    }
+
+    /// Returns the interval of positions in [`order`](Self::order) covered
+    /// by the given node and its descendants. The node itself is at the start
+    /// of the interval.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn interval(&self, id: NodeId) -> ops::Range<usize> {
+        self.enter[id.index()]..self.exit[id.index()]
+    }
+
+    /// Returns `true` if `a` is a proper ancestor of `b`. A node is not its
+    /// own ancestor. This is an O(1) operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either id is out of range.
+    pub fn is_ancestor(&self, a: NodeId, b: NodeId) -> bool {
+        let b_enter = self.enter[b.index()];
+        self.enter[a.index()] < b_enter && b_enter < self.exit[a.index()]
+    }
+
+    /// Returns the given node followed by all of its descendants, in
+    /// preorder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn subtree(&self, id: NodeId) -> &[NodeId] {
+        &self.order[self.interval(id)]
+    }
+
+    /// Returns all descendants of the given node in preorder, not including
+    /// the node itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn descendants(&self, id: NodeId) -> &[NodeId] {
+        &self.subtree(id)[1..]
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+
+use crate::ApterTree;
+
+/// A `Display` adapter that draws a tree like the `tree` command, created by
+/// [`ApterTree::pretty`].
+pub struct Pretty<'a, T, F> {
+    tree: &'a ApterTree<T>,
+    label: F,
+    ascii: bool,
+    max_depth: Option<usize>,
+    max_children: Option<usize>,
+}
+
+impl<T> ApterTree<T> {
+    /// Returns a `Display` adapter that draws the tree with box-drawing
+    /// characters, one node per line, using `label` to display each value.
+    /// Each root of a forest starts its own tree. Nodes on a cycle, which
+    /// belong to no root's tree, are not drawn.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// tree.insert("a1", a);
+    /// tree.insert("a2", a);
+    /// tree.insert("b", root);
+    /// assert_eq!(
+    ///     tree.pretty(|&v| v).to_string(),
+    ///     "root\n├── a\n│   ├── a1\n│   └── a2\n└── b\n",
+    /// );
+    /// assert_eq!(
+    ///     tree.pretty(|&v| v).ascii(true).max_depth(1).to_string(),
+    ///     "root\n|-- a\n|   `-- ... 2 more\n`-- b\n",
+    /// );
+    /// ```
+    ///
+    /// Labels may borrow from the values they display:
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root(("src".to_string(), 2));
+    /// tree.insert(("lib.rs".to_string(), 1), root);
+    /// assert_eq!(
+    ///     tree.pretty(|(name, _)| name.as_str()).to_string(),
+    ///     "src\n└── lib.rs\n",
+    /// );
+    /// ```
+    pub fn pretty<'a, L, F>(&'a self, label: F) -> Pretty<'a, T, F>
+    where
+        L: fmt::Display,
+        F: Fn(&'a T) -> L,
+    {
+        Pretty {
+            tree: self,
+            label,
+            ascii: false,
+            max_depth: None,
+            max_children: None,
+        }
+    }
+}
+
+impl<T, F> Pretty<'_, T, F> {
+    /// Draws the tree with ASCII characters only, for terminals and fonts
+    /// without box-drawing characters.
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Stops drawing below the given depth, where roots have depth 0. The
+    /// children of a node at this depth are summarised as "… N more".
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Draws at most the given number of children of each node, summarising
+    /// the rest as "… N more".
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root(0);
+    /// for i in 1..=10 {
+    ///     tree.insert(i, root);
+    /// }
+    /// assert_eq!(
+    ///     tree.pretty(|&v| v).max_children(2).to_string(),
+    ///     "0\n├── 1\n├── 2\n└── … 8 more\n",
+    /// );
+    /// ```
+    pub fn max_children(mut self, max_children: usize) -> Self {
+        self.max_children = Some(max_children);
+        self
+    }
+}
+
+impl<'a, T, L, F> fmt::Display for Pretty<'a, T, F>
+where
+    L: fmt::Display,
+    F: Fn(&'a T) -> L,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        enum Line {
+            Node(usize),
+            More(usize),
+        }
+
+        let (branch, last_branch, pipe, blank, ellipsis) = if self.ascii {
+            ("|-- ", "`-- ", "|   ", "    ", "...")
+        } else {
+            ("├── ", "└── ", "│   ", "    ", "…")
+        };
+
+        let tree: &'a ApterTree<T> = self.tree;
+        let index = tree.index_children();
+        // (line, depth, whether it is the last line among its siblings)
+        let mut stack: Vec<_> = index
+            .roots()
+            .iter()
+            .rev()
+            .map(|&root| (Line::Node(root), 0, true))
+            .collect();
+        // whether the ancestor at each depth of the current line was the last
+        // among its siblings, which decides between a pipe and a blank
+        let mut last_at_depth: Vec<bool> = vec![];
+
+        while let Some((line, depth, last)) = stack.pop() {
+            last_at_depth.truncate(depth);
+            if depth > 0 {
+                for &ancestor_last in &last_at_depth[1..] {
+                    f.write_str(if ancestor_last { blank } else { pipe })?;
+                }
+                f.write_str(if last { last_branch } else { branch })?;
+            }
+            last_at_depth.push(last);
+
+            let idx = match line {
+                Line::Node(idx) => idx,
+                Line::More(count) => {
+                    writeln!(f, "{ellipsis} {count} more")?;
+                    continue;
+                }
+            };
+            writeln!(f, "{}", (self.label)(&tree.d[idx]))?;
+
+            let children = index.children(idx);
+            if children.is_empty() {
+                continue;
+            }
+            if self.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                stack.push((Line::More(children.len()), depth + 1, true));
+                continue;
+            }
+            let shown = children.len().min(self.max_children.unwrap_or(usize::MAX));
+            if shown < children.len() {
+                stack.push((Line::More(children.len() - shown), depth + 1, true));
+            }
+            for (i, &child) in children[..shown].iter().enumerate().rev() {
+                let last = i + 1 == children.len();
+                stack.push((Line::Node(child), depth + 1, last));
+            }
+        }
+        Ok(())
+    }
+}
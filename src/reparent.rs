@@ -0,0 +1,93 @@
+use crate::{ApterError, ApterTree, NodeId};
+
+impl<T> ApterTree<T> {
+    /// Moves the given node, along with all of its descendants, under a new
+    /// parent. Unlike writing to `p` directly, this returns an error instead
+    /// of creating a cycle if `new_parent` is the node itself or one of its
+    /// descendants. This is O(depth).
+    ///
+    /// ```rust
+    /// use apter::{ApterError, ApterTree};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let b = tree.insert("b", root);
+    /// let a1 = tree.insert("a1", a);
+    ///
+    /// tree.move_subtree(a, b).unwrap();
+    /// assert_eq!(tree.parent_of(a), Some(b));
+    /// assert_eq!(
+    ///     tree.move_subtree(a, a1),
+    ///     Err(ApterError::Cycle { nodes: vec![a, a1] }),
+    /// );
+    /// ```
+    pub fn move_subtree(&mut self, id: NodeId, new_parent: NodeId) -> Result<(), ApterError> {
+        self.check(id)?;
+        self.check(new_parent)?;
+        self.check_reparent(id, new_parent.index())?;
+        self.p[id.index()] = new_parent.index();
+        self.invalidate_child_index();
+        Ok(())
+    }
+
+    /// Exchanges the parents of `a` and `b`, so that each node (along with
+    /// its descendants) takes the other's place in the hierarchy. Returns an
+    /// error instead of creating a cycle, e.g. if one node is an ancestor of
+    /// the other. This is O(depth).
+    ///
+    /// ```rust
+    /// use apter::{ApterError, ApterTree};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let b = tree.insert("b", root);
+    /// let a1 = tree.insert("a1", a);
+    /// let a2 = tree.insert("a2", a1);
+    /// let b1 = tree.insert("b1", b);
+    ///
+    /// tree.swap_parents(a1, b1).unwrap();
+    /// assert_eq!(tree.parent_of(a1), Some(b));
+    /// assert_eq!(tree.parent_of(b1), Some(a));
+    /// tree.swap_parents(a1, b1).unwrap();
+    ///
+    /// // `a` is the parent of `a1`, so it would become its own parent
+    /// assert_eq!(tree.swap_parents(a, a1), Err(ApterError::SelfParent { node: a }));
+    /// // `a` is an ancestor of `a2`, so it would end up below `a1`
+    /// let cycle = Err(ApterError::Cycle { nodes: vec![a, a1] });
+    /// assert_eq!(tree.swap_parents(a, a2), cycle);
+    /// assert_eq!(tree.swap_parents(a2, a), cycle);
+    /// assert_eq!(tree.p, vec![usize::MAX, 0, 0, 1, 3, 2]);
+    /// ```
+    pub fn swap_parents(&mut self, a: NodeId, b: NodeId) -> Result<(), ApterError> {
+        self.check(a)?;
+        self.check(b)?;
+        let (parent_a, parent_b) = (self.p[a.index()], self.p[b.index()]);
+        self.check_reparent(a, parent_b)?;
+        self.check_reparent(b, parent_a)?;
+        self.p.swap(a.index(), b.index());
+        self.invalidate_child_index();
+        Ok(())
+    }
+
+    /// Returns an error if making `new_parent_idx` the parent of `id` would
+    /// create a cycle.
+    fn check_reparent(&self, id: NodeId, new_parent_idx: usize) -> Result<(), ApterError> {
+        if new_parent_idx == id.index() {
+            return Err(ApterError::SelfParent { node: id });
+        }
+        if new_parent_idx >= self.len() {
+            return Ok(());
+        }
+
+        // the cycle that would form, in child-to-parent order
+        let new_parent = NodeId::new(new_parent_idx);
+        let mut nodes = vec![id, new_parent];
+        for ancestor in self.ancestors(new_parent).take(self.len()) {
+            if ancestor == id {
+                return Err(ApterError::Cycle { nodes });
+            }
+            nodes.push(ancestor);
+        }
+        Ok(())
+    }
+}
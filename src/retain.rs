@@ -0,0 +1,151 @@
+use crate::{ApterError, ApterTree, NodeId};
+
+/// Determines what happens to the children of a removed node that are
+/// themselves kept, when removing nodes with [`ApterTree::retain`] or
+/// [`ApterTree::delete_many`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OrphanPolicy {
+    /// Reattach orphaned children to their nearest surviving ancestor. If
+    /// there is none, they become roots.
+    #[default]
+    Reattach,
+    /// Remove orphaned children as well, along with all of their descendants.
+    Cascade,
+    /// Leave the tree unchanged and return [`ApterError::HasChildren`].
+    Error,
+}
+
+impl<T> ApterTree<T> {
+    /// Keeps only the nodes for which `f` returns `true`, compacting the tree
+    /// in a single O(n) pass. Children of removed nodes are handled according
+    /// to `policy`.
+    ///
+    /// Returns the old-to-new index mapping, indexed by the old raw index,
+    /// with `None` for every removed node.
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, NodeId, OrphanPolicy};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let b = tree.insert("b", root);
+    /// tree.insert("a1", a);
+    ///
+    /// let remap = tree
+    ///     .retain(OrphanPolicy::Reattach, |_, &v| v != "a")
+    ///     .unwrap();
+    /// assert_eq!(tree.d, vec!["root", "b", "a1"]);
+    /// assert_eq!(tree.p, vec![usize::MAX, 0, 0]);
+    /// assert_eq!(remap[b.index()], Some(NodeId::new(1)));
+    /// assert_eq!(remap[a.index()], None);
+    /// ```
+    pub fn retain(
+        &mut self,
+        policy: OrphanPolicy,
+        mut f: impl FnMut(NodeId, &T) -> bool,
+    ) -> Result<Vec<Option<NodeId>>, ApterError> {
+        let remove = self.iter().map(|(id, v)| !f(id, v)).collect();
+        self.remove_flagged(remove, policy)
+    }
+
+    /// Deletes all of the given nodes in a single O(n) compaction pass.
+    /// Children of removed nodes are handled according to `policy`, and ids
+    /// that are out of range are ignored.
+    ///
+    /// Returns the old-to-new index mapping, indexed by the old raw index,
+    /// with `None` for every removed node.
+    ///
+    /// ```rust
+    /// use apter::{ApterError, ApterTree, NodeId, OrphanPolicy};
+    /// // "a1" is stored before its parent "a"
+    /// let mut tree = ApterTree::new();
+    /// tree.d = vec!["a1", "root", "a", "b"];
+    /// tree.p = vec![2, usize::MAX, 1, 1];
+    /// let a = NodeId::new(2);
+    /// let ids = |remap: Vec<Option<NodeId>>| -> Vec<_> {
+    ///     remap.into_iter().map(|id| id.map(NodeId::index)).collect()
+    /// };
+    ///
+    /// let mut reattached = tree.clone();
+    /// let remap = reattached.delete_many(&[a], OrphanPolicy::Reattach).unwrap();
+    /// assert_eq!(reattached.d, vec!["a1", "root", "b"]);
+    /// assert_eq!(reattached.p, vec![1, usize::MAX, 1]);
+    /// assert_eq!(ids(remap), vec![Some(0), Some(1), None, Some(2)]);
+    ///
+    /// let mut cascaded = tree.clone();
+    /// let remap = cascaded.delete_many(&[a], OrphanPolicy::Cascade).unwrap();
+    /// assert_eq!(cascaded.d, vec!["root", "b"]);
+    /// assert_eq!(cascaded.p, vec![usize::MAX, 0]);
+    /// assert_eq!(ids(remap), vec![None, Some(0), None, Some(1)]);
+    ///
+    /// let mut unchanged = tree.clone();
+    /// assert_eq!(
+    ///     unchanged.delete_many(&[a], OrphanPolicy::Error),
+    ///     Err(ApterError::HasChildren { node: a, child: NodeId::new(0) }),
+    /// );
+    /// assert_eq!(unchanged.d, tree.d);
+    /// assert_eq!(unchanged.p, tree.p);
+    /// ```
+    pub fn delete_many(
+        &mut self,
+        ids: &[NodeId],
+        policy: OrphanPolicy,
+    ) -> Result<Vec<Option<NodeId>>, ApterError> {
+        let mut remove = vec![false; self.len()];
+        for id in ids {
+            if let Some(flag) = remove.get_mut(id.index()) {
+                *flag = true;
+            }
+        }
+        self.remove_flagged(remove, policy)
+    }
+
+    fn remove_flagged(
+        &mut self,
+        mut remove: Vec<bool>,
+        policy: OrphanPolicy,
+    ) -> Result<Vec<Option<NodeId>>, ApterError> {
+        let len = self.len();
+        let is_removed = |remove: &[bool], idx: usize| idx < len && remove[idx];
+
+        match policy {
+            OrphanPolicy::Reattach => {
+                // `anchor[idx]` is the nearest kept node at or above `idx`
+                let order = self.index_children().parents_first();
+                let mut anchor = vec![usize::MAX; len];
+                for idx in order {
+                    let parent = self.p[idx];
+                    if !remove[idx] {
+                        anchor[idx] = idx;
+                    } else if parent < len {
+                        anchor[idx] = anchor[parent];
+                    }
+                    if !remove[idx] && is_removed(&remove, parent) {
+                        self.p[idx] = anchor[parent];
+                    }
+                }
+            }
+            OrphanPolicy::Cascade => {
+                let order = self.index_children().parents_first();
+                for idx in order {
+                    if is_removed(&remove, self.p[idx]) {
+                        remove[idx] = true;
+                    }
+                }
+            }
+            OrphanPolicy::Error => {
+                for (idx, &parent) in self.p.iter().enumerate() {
+                    if !remove[idx] && is_removed(&remove, parent) {
+                        return Err(ApterError::HasChildren {
+                            node: NodeId::new(parent),
+                            child: NodeId::new(idx),
+                        });
+                    }
+                }
+            }
+        }
+
+        let (_, remap) = self.compact(&remove);
+        Ok(remap)
+    }
+}
@@ -0,0 +1,169 @@
+//! Serde support, enabled by the `serde` feature.
+//!
+//! [`ApterTree`] implements `Serialize` and `Deserialize` using a compact
+//! columnar representation that mirrors the `d` and `p` columns, with roots
+//! having a `null` parent instead of `usize::MAX`:
+//!
+//! ```rust
+//! use apter::ApterTree;
+//! let mut tree = ApterTree::new();
+//! let root = tree.insert_root("root");
+//! tree.insert("a", root);
+//!
+//! let json = serde_json::to_string(&tree).unwrap();
+//! assert_eq!(json, r#"{"d":["root","a"],"p":[null,0]}"#);
+//!
+//! let tree: ApterTree<String> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(tree.p, vec![usize::MAX, 0]);
+//! assert!(serde_json::from_str::<ApterTree<String>>(r#"{"d":["a"],"p":[0]}"#).is_err());
+//! ```
+//!
+//! The [`nested`] module provides a nested representation instead, for use
+//! with `#[serde(with = "apter::serde::nested")]`.
+
+use ::serde::{de, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{ApterTree, ChildIndex, NestedNode};
+
+#[derive(Serialize)]
+#[serde(rename = "ApterTree")]
+struct ColumnsRef<'a, T> {
+    d: &'a [T],
+    p: ParentsRef<'a>,
+}
+
+struct ParentsRef<'a>(&'a [usize]);
+
+impl Serialize for ParentsRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let parents = self.0.iter();
+        serializer.collect_seq(parents.map(|&parent| (parent != usize::MAX).then_some(parent)))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "ApterTree")]
+struct Columns<T> {
+    d: Vec<T>,
+    p: Vec<Option<usize>>,
+}
+
+impl<T: Serialize> Serialize for ApterTree<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ColumnsRef {
+            d: &self.d,
+            p: ParentsRef(&self.p),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserializes the columnar representation, and fails unless the result
+/// passes [`ApterTree::validate`].
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for ApterTree<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let columns = Columns::deserialize(deserializer)?;
+        let mut tree = ApterTree::new();
+        tree.d = columns.d;
+        tree.p = columns
+            .p
+            .into_iter()
+            .map(|parent| parent.unwrap_or(usize::MAX))
+            .collect();
+        tree.validate().map_err(de::Error::custom)?;
+        Ok(tree)
+    }
+}
+
+/// The nested representation, for use with
+/// `#[serde(with = "apter::serde::nested")]`.
+///
+/// A tree is represented as a sequence of its roots, each of the form
+/// `{"value": ..., "children": [...]}` like a [`NestedNode`]. Siblings keep
+/// their order, and the `children` field may be omitted for leaves when
+/// deserializing.
+///
+/// ```rust
+/// use apter::ApterTree;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Document {
+///     #[serde(with = "apter::serde::nested")]
+///     outline: ApterTree<String>,
+/// }
+///
+/// let mut outline = ApterTree::new();
+/// let root = outline.insert_root("intro".to_string());
+/// outline.insert("motivation".to_string(), root);
+///
+/// let json = serde_json::to_string(&Document { outline }).unwrap();
+/// assert_eq!(
+///     json,
+///     r#"{"outline":[{"value":"intro","children":[{"value":"motivation","children":[]}]}]}"#,
+/// );
+/// let doc: Document = serde_json::from_str(&json).unwrap();
+/// assert_eq!(doc.outline.p, vec![usize::MAX, 0]);
+/// ```
+///
+/// Serializers and deserializers handle nesting recursively, so very deep
+/// trees may exceed their recursion limits; prefer the columnar
+/// representation for those.
+pub mod nested {
+    use super::*;
+
+    struct NodeRef<'a, T> {
+        tree: &'a ApterTree<T>,
+        index: &'a ChildIndex,
+        idx: usize,
+    }
+
+    impl<T: Serialize> Serialize for NodeRef<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut node = serializer.serialize_struct("Node", 2)?;
+            node.serialize_field("value", &self.tree.d[self.idx])?;
+            node.serialize_field("children", &ChildrenRef { node: self })?;
+            node.end()
+        }
+    }
+
+    struct ChildrenRef<'a, T> {
+        node: &'a NodeRef<'a, T>,
+    }
+
+    impl<T: Serialize> Serialize for ChildrenRef<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let NodeRef { tree, index, idx } = *self.node;
+            serializer.collect_seq(index.children(idx).iter().map(|&idx| NodeRef {
+                tree,
+                index,
+                idx,
+            }))
+        }
+    }
+
+    /// Serializes a tree as a sequence of nested root nodes.
+    pub fn serialize<T, S>(tree: &ApterTree<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        let index = tree.index_children();
+        serializer.collect_seq(index.roots().iter().map(|&idx| NodeRef {
+            tree,
+            index: &index,
+            idx,
+        }))
+    }
+
+    /// Deserializes a tree from a sequence of nested root nodes. Nodes are
+    /// stored in preorder.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<ApterTree<T>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let roots = Vec::<NestedNode<T>>::deserialize(deserializer)?;
+        Ok(ApterTree::join(roots.into_iter().map(ApterTree::from)))
+    }
+}
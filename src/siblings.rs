@@ -0,0 +1,237 @@
+use crate::{ApterError, ApterTree, NodeId};
+
+/// Sibling order is index order: a node comes before its siblings with higher
+/// indices. The insertion methods below keep this order by physically
+/// inserting the new node at the right position in `d` and `p`, shifting
+/// every later node up by one. Like [`ApterTree::delete`], this is O(n), and
+/// every `NodeId` at or after the insertion point now refers to the next node.
+impl<T> ApterTree<T> {
+    /// Inserts a new item as the sibling immediately before `sibling`, and
+    /// returns its id. `sibling` itself moves up by one index.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let c = tree.insert("c", root);
+    /// tree.insert_before(c, "b");
+    /// tree.insert_child_at(root, 0, "first");
+    /// let names: Vec<_> = tree.children(root).map(|id| tree[id]).collect();
+    /// assert_eq!(names, vec!["first", "a", "b", "c"]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sibling` is out of range.
+    pub fn insert_before(&mut self, sibling: NodeId, v: T) -> NodeId {
+        self.try_insert_before(sibling, v)
+            .unwrap_or_else(|err| panic!("cannot insert node: {err}"))
+    }
+
+    /// Like [`insert_before`](Self::insert_before), but returns an error
+    /// instead of panicking if `sibling` is out of range.
+    pub fn try_insert_before(&mut self, sibling: NodeId, v: T) -> Result<NodeId, ApterError> {
+        self.check(sibling)?;
+        Ok(self.insert_at(sibling.index(), v, self.p[sibling.index()]))
+    }
+
+    /// Inserts a new item as the sibling immediately after `sibling`, and
+    /// returns its id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sibling` is out of range.
+    pub fn insert_after(&mut self, sibling: NodeId, v: T) -> NodeId {
+        self.try_insert_after(sibling, v)
+            .unwrap_or_else(|err| panic!("cannot insert node: {err}"))
+    }
+
+    /// Like [`insert_after`](Self::insert_after), but returns an error
+    /// instead of panicking if `sibling` is out of range.
+    pub fn try_insert_after(&mut self, sibling: NodeId, v: T) -> Result<NodeId, ApterError> {
+        self.check(sibling)?;
+        Ok(self.insert_at(sibling.index() + 1, v, self.p[sibling.index()]))
+    }
+
+    /// Inserts a new item as the child of `parent` at the given position
+    /// among its children, and returns its id. A position equal to the
+    /// number of children appends the new item after all existing children.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parent` is out of range, or if `position` is greater than
+    /// the number of children.
+    pub fn insert_child_at(&mut self, parent: NodeId, position: usize, v: T) -> NodeId {
+        self.try_insert_child_at(parent, position, v)
+            .unwrap_or_else(|err| panic!("cannot insert node: {err}"))
+    }
+
+    /// Like [`insert_child_at`](Self::insert_child_at), but returns an error
+    /// instead of panicking if `parent` or `position` is out of range.
+    pub fn try_insert_child_at(
+        &mut self,
+        parent: NodeId,
+        position: usize,
+        v: T,
+    ) -> Result<NodeId, ApterError> {
+        self.check(parent)?;
+        let children: Vec<_> = self.children(parent).collect();
+        let idx = match children.get(position) {
+            Some(&child) => child.index(),
+            None if position == children.len() => {
+                children.last().map_or(self.len(), |last| last.index() + 1)
+            }
+            None => {
+                return Err(ApterError::IndexOutOfBounds {
+                    index: position,
+                    len: children.len() + 1,
+                })
+            }
+        };
+        Ok(self.insert_at(idx, v, parent.index()))
+    }
+
+    /// Returns the position of the given node among its siblings, so that
+    /// the first child of a parent has position 0. Roots are siblings of
+    /// each other.
+    ///
+    /// This and the other sibling navigation methods are O(log k) for a
+    /// parent with k children if the child index is enabled (see
+    /// [`enable_child_index`](Self::enable_child_index)), and O(n) otherwise.
+    /// As with [`children`](Self::children), call
+    /// [`invalidate_child_index`](Self::invalidate_child_index) after
+    /// modifying `p` directly; until then, the results are unspecified.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// tree.enable_child_index();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let b = tree.insert("b", root);
+    /// let c = tree.insert("c", root);
+    /// assert_eq!(tree.child_index(b), 1);
+    /// assert_eq!(tree.next_sibling(b), Some(c));
+    /// assert_eq!(tree.prev_sibling(a), None);
+    /// assert_eq!(tree.siblings(b).collect::<Vec<_>>(), vec![a, c]);
+    /// assert_eq!(tree.first_child(root), Some(a));
+    /// assert_eq!(tree.last_child(root), Some(c));
+    /// assert_eq!(tree.first_child(a), None);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn child_index(&self, id: NodeId) -> usize {
+        match self.cached_siblings(id) {
+            Some((_, pos)) => pos,
+            None => (0..id.index())
+                .filter(|&idx| self.same_parent(idx, id.index()))
+                .count(),
+        }
+    }
+
+    /// Returns the sibling immediately after the given node, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        let idx = match self.cached_siblings(id) {
+            Some((siblings, pos)) => siblings.get(pos + 1).copied(),
+            None => (id.index() + 1..self.len()).find(|&idx| self.same_parent(idx, id.index())),
+        };
+        idx.map(NodeId::new)
+    }
+
+    /// Returns the sibling immediately before the given node, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+        let idx = match self.cached_siblings(id) {
+            Some((siblings, pos)) => pos.checked_sub(1).map(|pos| siblings[pos]),
+            None => (0..id.index())
+                .rev()
+                .find(|&idx| self.same_parent(idx, id.index())),
+        };
+        idx.map(NodeId::new)
+    }
+
+    /// Returns an iterator through all siblings of the given node in sibling
+    /// order, not including the node itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of range.
+    pub fn siblings(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let parent = self.parent_of(id);
+        let children = parent.map(|parent| self.children(parent));
+        let roots = parent.is_none().then(|| self.roots());
+        children
+            .into_iter()
+            .flatten()
+            .chain(roots.into_iter().flatten())
+            .filter(move |&sibling| sibling != id)
+    }
+
+    /// Returns the first child of the given node, if any.
+    pub fn first_child(&self, id: NodeId) -> Option<NodeId> {
+        self.children(id).next()
+    }
+
+    /// Returns the last child of the given node, if any.
+    pub fn last_child(&self, id: NodeId) -> Option<NodeId> {
+        let idx = match self.cached_child_index() {
+            Some(index) => index.children(id.index()).last().copied(),
+            None => (0..self.len()).rev().find(|&idx| self.p[idx] == id.index()),
+        };
+        idx.map(NodeId::new)
+    }
+
+    /// Returns the siblings of `id`, including `id` itself, and the position
+    /// of `id` among them, from the cached child index if it is enabled.
+    fn cached_siblings(&self, id: NodeId) -> Option<(&[usize], usize)> {
+        let parent_idx = self.p[id.index()];
+        let index = self.cached_child_index()?;
+        let siblings = if parent_idx < self.len() {
+            index.children(parent_idx)
+        } else {
+            index.roots()
+        };
+        // a stale index may not list `id`, which gives wrong results rather
+        // than a panic
+        let pos = siblings
+            .binary_search(&id.index())
+            .unwrap_or_else(|pos| pos);
+        Some((siblings, pos))
+    }
+
+    /// Returns `true` if both nodes have the same parent, or are both roots.
+    fn same_parent(&self, a: usize, b: usize) -> bool {
+        let (parent_a, parent_b) = (self.p[a], self.p[b]);
+        parent_a == parent_b || (parent_a >= self.len() && parent_b >= self.len())
+    }
+
+    /// Inserts a new item at index `idx`, shifting every later node up by
+    /// one and rewriting parent indices to match.
+    fn insert_at(&mut self, idx: usize, v: T, parent_idx: usize) -> NodeId {
+        let len = self.len();
+        let shift = |parent: usize| {
+            if parent >= idx && parent < len {
+                parent + 1
+            } else {
+                parent
+            }
+        };
+        for parent in &mut self.p {
+            *parent = shift(*parent);
+        }
+        self.d.insert(idx, v);
+        self.p.insert(idx, shift(parent_idx));
+        self.invalidate_child_index();
+        NodeId::new(idx)
+    }
+}
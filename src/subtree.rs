@@ -0,0 +1,163 @@
+use std::{iter, ops};
+
+use crate::{ApterError, ApterTree, NodeId};
+
+/// An old-to-new index mapping, indexed by the old raw index.
+type Remap = Vec<Option<NodeId>>;
+
+impl<T> ApterTree<T> {
+    /// Returns a copy of the subtree rooted at `id` as a standalone tree,
+    /// together with the old-to-new index mapping. The mapping is indexed by
+    /// the old raw index, with `None` for nodes outside the subtree.
+    ///
+    /// The extracted nodes are stored in preorder, so `id` becomes the root
+    /// at index 0 with a parent index of `usize::MAX`. Returns `None` if `id`
+    /// is out of range.
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, NodeId};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// tree.insert("b", root);
+    /// let a1 = tree.insert("a1", a);
+    ///
+    /// let (branch, mapping) = tree.subtree(a).unwrap();
+    /// assert_eq!(branch.d, vec!["a", "a1"]);
+    /// assert_eq!(branch.p, vec![usize::MAX, 0]);
+    /// assert_eq!(mapping[a1.index()], Some(NodeId::new(1)));
+    /// assert_eq!(mapping[root.index()], None);
+    /// ```
+    pub fn subtree(&self, id: NodeId) -> Option<(ApterTree<T>, Remap)>
+    where
+        T: Clone,
+    {
+        let (order, mapping) = self.subtree_order(id)?;
+        let mut tree = ApterTree::new();
+        for &idx in &order {
+            tree.d.push(self.d[idx].clone());
+            tree.p.push(self.mapped_parent(idx, id, &mapping));
+        }
+        Some((tree, mapping))
+    }
+
+    /// Like [`subtree`](Self::subtree), but moves the subtree out of this
+    /// tree instead of copying it. The remaining nodes are compacted in a
+    /// single O(n) pass, as with [`delete_subtree`](Self::delete_subtree).
+    ///
+    /// Returns the extracted tree and two mappings, both indexed by the old
+    /// raw index: one into the extracted tree, and one into the remaining
+    /// tree, each with `None` for nodes that ended up in the other tree.
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, NodeId};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let b = tree.insert("b", root);
+    /// let a1 = tree.insert("a1", a);
+    ///
+    /// let (branch, extracted, remaining) = tree.take_subtree(a).unwrap();
+    /// assert_eq!(branch.d, vec!["a", "a1"]);
+    /// assert_eq!(branch.p, vec![usize::MAX, 0]);
+    /// assert_eq!(tree.d, vec!["root", "b"]);
+    /// assert_eq!(tree.p, vec![usize::MAX, 0]);
+    /// assert_eq!(extracted[a1.index()], Some(NodeId::new(1)));
+    /// assert_eq!(extracted[b.index()], None);
+    /// assert_eq!(remaining[b.index()], Some(NodeId::new(1)));
+    /// assert_eq!(remaining[a1.index()], None);
+    /// assert!(tree.take_subtree(NodeId::new(2)).is_none());
+    /// ```
+    pub fn take_subtree(&mut self, id: NodeId) -> Option<(ApterTree<T>, Remap, Remap)> {
+        let (order, mapping) = self.subtree_order(id)?;
+        let mut tree = ApterTree::new();
+        tree.p = order
+            .iter()
+            .map(|&idx| self.mapped_parent(idx, id, &mapping))
+            .collect();
+
+        let remove: Vec<bool> = mapping.iter().map(Option::is_some).collect();
+        let (removed, remap) = self.compact(&remove);
+
+        // `removed` is in index order, so sort it back into preorder
+        let mut slots: Vec<Option<T>> = iter::repeat_with(|| None).take(order.len()).collect();
+        let new_indices = mapping.iter().flatten();
+        for (new_id, v) in new_indices.zip(removed) {
+            slots[new_id.index()] = Some(v);
+        }
+        tree.d = slots.into_iter().flatten().collect();
+
+        Some((tree, mapping, remap))
+    }
+
+    /// Appends all nodes of `other` to this tree, attaching the roots of
+    /// `other` as children of `under`. Returns the range of indices where the
+    /// grafted nodes landed; the nodes keep their order, so node `i` of
+    /// `other` ends up at index `range.start + i`.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// tree.insert("a", root);
+    ///
+    /// let mut branch = ApterTree::new();
+    /// let b = branch.insert_root("b");
+    /// branch.insert("b1", b);
+    ///
+    /// assert_eq!(tree.graft(branch, root), 2..4);
+    /// assert_eq!(tree.d, vec!["root", "a", "b", "b1"]);
+    /// assert_eq!(tree.p, vec![usize::MAX, 0, 0, 2]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `under` is out of range.
+    pub fn graft(&mut self, other: ApterTree<T>, under: NodeId) -> ops::Range<usize> {
+        self.try_graft(other, under)
+            .unwrap_or_else(|err| panic!("cannot graft tree: {err}"))
+    }
+
+    /// Like [`graft`](Self::graft), but returns an error instead of panicking
+    /// if `under` is out of range.
+    pub fn try_graft(
+        &mut self,
+        other: ApterTree<T>,
+        under: NodeId,
+    ) -> Result<ops::Range<usize>, ApterError> {
+        self.check(under)?;
+        let offset = self.len();
+        let len = other.len();
+        self.d.extend(other.d);
+        self.p.extend(other.p.into_iter().map(|parent| {
+            if parent < len {
+                parent + offset
+            } else {
+                under.index()
+            }
+        }));
+        self.invalidate_child_index();
+        Ok(offset..self.len())
+    }
+
+    /// Returns the subtree rooted at `id` in preorder, along with the
+    /// mapping from old indices to preorder positions.
+    fn subtree_order(&self, id: NodeId) -> Option<(Vec<usize>, Remap)> {
+        if id.index() >= self.len() {
+            return None;
+        }
+        let order = self.index_children().preorder(id.index());
+        let mut mapping = vec![None; self.len()];
+        for (pos, &idx) in order.iter().enumerate() {
+            mapping[idx] = Some(NodeId::new(pos));
+        }
+        Some((order, mapping))
+    }
+
+    fn mapped_parent(&self, idx: usize, root: NodeId, mapping: &[Option<NodeId>]) -> usize {
+        if idx == root.index() {
+            return usize::MAX;
+        }
+        mapping[self.p[idx]].map_or(usize::MAX, NodeId::index)
+    }
+}
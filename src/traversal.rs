@@ -0,0 +1,267 @@
+use std::{borrow::Cow, collections::VecDeque, iter, mem, vec};
+
+use crate::{ApterTree, ChildIndex, NodeId};
+
+/// A depth-first preorder iterator over a subtree, created by
+/// [`ApterTree::preorder`].
+#[derive(Clone, Debug)]
+pub struct Preorder<'a, T> {
+    d: &'a [T],
+    index: Cow<'a, ChildIndex>,
+    stack: Vec<(usize, usize)>,
+}
+
+impl<'a, T> Iterator for Preorder<'a, T> {
+    type Item = (NodeId, &'a T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, depth) = self.stack.pop()?;
+        // no path in a well-formed tree is longer than the number of nodes,
+        // so this only stops descending into a cycle
+        if depth < self.d.len() {
+            let children = self.index.children(idx).iter().rev();
+            self.stack.extend(children.map(|&child| (child, depth + 1)));
+        }
+        Some((NodeId::new(idx), &self.d[idx], depth))
+    }
+}
+
+/// A depth-first postorder iterator over a subtree, created by
+/// [`ApterTree::postorder`].
+#[derive(Clone, Debug)]
+pub struct Postorder<'a, T> {
+    d: &'a [T],
+    index: Cow<'a, ChildIndex>,
+    // (node, depth, position of the next child to visit)
+    stack: Vec<(usize, usize, usize)>,
+}
+
+impl<'a, T> Iterator for Postorder<'a, T> {
+    type Item = (NodeId, &'a T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (idx, depth, next) = self.stack.last_mut()?;
+            let children = self.index.children(*idx);
+            if *next < children.len() && *depth < self.d.len() {
+                let child = (children[*next], *depth + 1, 0);
+                *next += 1;
+                self.stack.push(child);
+            } else {
+                let (idx, depth, _) = self.stack.pop()?;
+                return Some((NodeId::new(idx), &self.d[idx], depth));
+            }
+        }
+    }
+}
+
+/// A breadth-first iterator over a subtree, created by [`ApterTree::bfs`].
+#[derive(Clone, Debug)]
+pub struct Bfs<'a, T> {
+    d: &'a [T],
+    index: Cow<'a, ChildIndex>,
+    queue: VecDeque<(usize, usize)>,
+}
+
+impl<'a, T> Iterator for Bfs<'a, T> {
+    type Item = (NodeId, &'a T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, depth) = self.queue.pop_front()?;
+        if depth < self.d.len() {
+            let children = self.index.children(idx).iter();
+            self.queue.extend(children.map(|&child| (child, depth + 1)));
+        }
+        Some((NodeId::new(idx), &self.d[idx], depth))
+    }
+}
+
+/// An iterator over the levels of a subtree, created by
+/// [`ApterTree::levels`]. Each item holds the ids of all nodes at one depth.
+#[derive(Clone, Debug)]
+pub struct Levels<'a> {
+    index: Cow<'a, ChildIndex>,
+    level: Vec<NodeId>,
+    depth: usize,
+}
+
+impl Iterator for Levels<'_> {
+    type Item = Vec<NodeId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.level.is_empty() || self.depth > self.index.len() {
+            return None;
+        }
+        let next = self
+            .level
+            .iter()
+            .flat_map(|id| self.index.children(id.index()))
+            .map(|&child| NodeId::new(child))
+            .collect();
+        self.depth += 1;
+        Some(mem::replace(&mut self.level, next))
+    }
+}
+
+/// A depth-first preorder iterator over mutable references to a subtree,
+/// created by [`ApterTree::preorder_mut`].
+#[derive(Debug)]
+pub struct PreorderMut<'a, T>(OrderedMut<'a, T>);
+
+impl<'a, T> Iterator for PreorderMut<'a, T> {
+    type Item = (NodeId, &'a mut T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// A depth-first postorder iterator over mutable references to a subtree,
+/// created by [`ApterTree::postorder_mut`].
+#[derive(Debug)]
+pub struct PostorderMut<'a, T>(OrderedMut<'a, T>);
+
+impl<'a, T> Iterator for PostorderMut<'a, T> {
+    type Item = (NodeId, &'a mut T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Hands out mutable references to the elements of `d` in a precomputed
+/// order, each at most once.
+#[derive(Debug)]
+struct OrderedMut<'a, T> {
+    order: vec::IntoIter<(usize, usize)>,
+    slots: Vec<Option<&'a mut T>>,
+}
+
+impl<'a, T> OrderedMut<'a, T> {
+    fn new(d: &'a mut [T], order: Vec<(usize, usize)>) -> Self {
+        Self {
+            order: order.into_iter(),
+            slots: d.iter_mut().map(Some).collect(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for OrderedMut<'a, T> {
+    type Item = (NodeId, &'a mut T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.order.by_ref().find_map(|(idx, depth)| {
+            let v = self.slots[idx].take()?;
+            Some((NodeId::new(idx), v, depth))
+        })
+    }
+}
+
+impl<T> ApterTree<T> {
+    /// Returns a depth-first preorder iterator over the subtree rooted at
+    /// `root`, yielding each node's id, value and depth relative to `root`.
+    /// Parents are visited before their children, and siblings in index
+    /// order.
+    ///
+    /// The traversal uses an explicit stack rather than recursion, and builds
+    /// on the child index (see [`index_children`](Self::index_children)), so it
+    /// runs in O(n) even on very deep trees.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// tree.insert("b", root);
+    /// tree.insert("a1", a);
+    ///
+    /// let pre: Vec<_> = tree.preorder(root).map(|(_, &v, depth)| (v, depth)).collect();
+    /// assert_eq!(pre, vec![("root", 0), ("a", 1), ("a1", 2), ("b", 1)]);
+    /// let post: Vec<_> = tree.postorder(root).map(|(_, &v, _)| v).collect();
+    /// assert_eq!(post, vec!["a1", "a", "b", "root"]);
+    /// ```
+    pub fn preorder(&self, root: NodeId) -> Preorder<'_, T> {
+        Preorder {
+            d: &self.d,
+            index: self.index_children(),
+            stack: iter::once((root.index(), 0))
+                .filter(|&(idx, _)| idx < self.len())
+                .collect(),
+        }
+    }
+
+    /// Returns a depth-first postorder iterator over the subtree rooted at
+    /// `root`, yielding each node's id, value and depth relative to `root`.
+    /// Children are visited before their parents.
+    ///
+    /// Like [`preorder`](Self::preorder), this does not recurse and runs in
+    /// O(n).
+    pub fn postorder(&self, root: NodeId) -> Postorder<'_, T> {
+        Postorder {
+            d: &self.d,
+            index: self.index_children(),
+            stack: iter::once((root.index(), 0, 0))
+                .filter(|&(idx, _, _)| idx < self.len())
+                .collect(),
+        }
+    }
+
+    /// Returns a preorder iterator over mutable references to the subtree
+    /// rooted at `root`. See [`preorder`](Self::preorder).
+    pub fn preorder_mut(&mut self, root: NodeId) -> PreorderMut<'_, T> {
+        let order = self
+            .preorder(root)
+            .map(|(id, _, depth)| (id.index(), depth))
+            .collect();
+        PreorderMut(OrderedMut::new(&mut self.d, order))
+    }
+
+    /// Returns a postorder iterator over mutable references to the subtree
+    /// rooted at `root`. See [`postorder`](Self::postorder).
+    pub fn postorder_mut(&mut self, root: NodeId) -> PostorderMut<'_, T> {
+        let order = self
+            .postorder(root)
+            .map(|(id, _, depth)| (id.index(), depth))
+            .collect();
+        PostorderMut(OrderedMut::new(&mut self.d, order))
+    }
+
+    /// Returns a breadth-first iterator over the subtree rooted at `root`,
+    /// yielding each node's id, value and depth relative to `root`. Nodes
+    /// are visited level by level, and in index order within each parent.
+    /// This runs in O(n).
+    pub fn bfs(&self, root: NodeId) -> Bfs<'_, T> {
+        Bfs {
+            d: &self.d,
+            index: self.index_children(),
+            queue: iter::once((root.index(), 0))
+                .filter(|&(idx, _)| idx < self.len())
+                .collect(),
+        }
+    }
+
+    /// Returns an iterator over the levels of the subtree rooted at `root`.
+    /// The first item is `vec![root]`, the second holds its children, and so
+    /// on, in the same order as [`bfs`](Self::bfs). This runs in O(n) total.
+    ///
+    /// ```rust
+    /// use apter::ApterTree;
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let b = tree.insert("b", root);
+    /// let a1 = tree.insert("a1", a);
+    ///
+    /// let levels: Vec<_> = tree.levels(root).collect();
+    /// assert_eq!(levels, vec![vec![root], vec![a, b], vec![a1]]);
+    /// ```
+    pub fn levels(&self, root: NodeId) -> Levels<'_> {
+        Levels {
+            index: self.index_children(),
+            level: iter::once(root)
+                .filter(|id| id.index() < self.len())
+                .collect(),
+            depth: 0,
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{ApterError, ApterTree, NodeId};
+
+impl<T> ApterTree<T> {
+    /// Checks that the tree is structurally sound: `d` and `p` have the same
+    /// length, and every parent index is either `usize::MAX` or refers to
+    /// another node without forming a cycle. Forests with several roots are
+    /// allowed. Returns the first problem found, in the order of
+    /// [`validation_errors`](Self::validation_errors). This runs in O(n).
+    ///
+    /// Other methods assume a valid tree, so trees loaded from untrusted
+    /// sources should be validated first.
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, NodeId, ApterError};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// let a = tree.insert("a", root);
+    /// let b = tree.insert("b", a);
+    /// assert_eq!(tree.validate(), Ok(()));
+    ///
+    /// tree.p[a.index()] = b.index();
+    /// assert_eq!(tree.validate(), Err(ApterError::Cycle { nodes: vec![a, b] }));
+    /// ```
+    pub fn validate(&self) -> Result<(), ApterError> {
+        match self.validation_errors().into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns every structural problem that [`validate`](Self::validate)
+    /// checks for, or an empty vector if the tree is sound. A length
+    /// mismatch comes first, followed by self-parents and out-of-range
+    /// parents in index order, and then each cycle once. This runs in O(n).
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, NodeId, ApterError};
+    /// let mut tree = ApterTree::new();
+    /// tree.d = vec!["a", "b", "c", "d", "e"];
+    /// tree.p = vec![usize::MAX, 1, 7, 4, 3, 0];
+    /// assert_eq!(
+    ///     tree.validation_errors(),
+    ///     vec![
+    ///         ApterError::LengthMismatch { values: 5, parents: 6 },
+    ///         ApterError::SelfParent { node: NodeId::new(1) },
+    ///         ApterError::InvalidParent { node: NodeId::new(2), parent: 7 },
+    ///         ApterError::Cycle { nodes: vec![NodeId::new(3), NodeId::new(4)] },
+    ///     ],
+    /// );
+    /// assert_eq!(
+    ///     tree.validate(),
+    ///     Err(ApterError::LengthMismatch { values: 5, parents: 6 }),
+    /// );
+    /// ```
+    pub fn validation_errors(&self) -> Vec<ApterError> {
+        let mut errors = vec![];
+        let len = self.len();
+        if self.d.len() != len {
+            errors.push(ApterError::LengthMismatch {
+                values: self.d.len(),
+                parents: len,
+            });
+        }
+
+        for (idx, &parent) in self.p.iter().enumerate() {
+            if parent == idx {
+                errors.push(ApterError::SelfParent {
+                    node: NodeId::new(idx),
+                });
+            } else if parent >= len && parent != usize::MAX {
+                errors.push(ApterError::InvalidParent {
+                    node: NodeId::new(idx),
+                    parent,
+                });
+            }
+        }
+
+        // `walk[idx]` is the 1-based start of the walk that first reached
+        // `idx`, or 0 if no walk has reached it yet. Each cycle is found by
+        // the first walk that enters it.
+        let mut walk = vec![0; len];
+        for start in 0..len {
+            let mut idx = start;
+            while idx < len && walk[idx] == 0 {
+                walk[idx] = start + 1;
+                idx = self.p[idx];
+            }
+            // self-parents have already been reported
+            if idx < len && walk[idx] == start + 1 && self.p[idx] != idx {
+                let cycle_start = idx;
+                let mut nodes = vec![NodeId::new(cycle_start)];
+                idx = self.p[cycle_start];
+                while idx != cycle_start {
+                    nodes.push(NodeId::new(idx));
+                    idx = self.p[idx];
+                }
+                errors.push(ApterError::Cycle { nodes });
+            }
+        }
+
+        errors
+    }
+
+    /// Like [`validate`](Self::validate), but additionally requires a
+    /// non-empty tree to have exactly one root.
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, ApterError};
+    /// let mut tree = ApterTree::new();
+    /// let a = tree.insert_root("a");
+    /// assert_eq!(tree.validate_rooted(), Ok(()));
+    /// let b = tree.insert_root("b");
+    /// assert_eq!(tree.validate(), Ok(()));
+    /// assert_eq!(
+    ///     tree.validate_rooted(),
+    ///     Err(ApterError::MultipleRoots { roots: vec![a, b] }),
+    /// );
+    /// ```
+    pub fn validate_rooted(&self) -> Result<(), ApterError> {
+        self.validate()?;
+        let roots: Vec<_> = self.keys().filter(|&id| self.is_root(id)).collect();
+        if roots.len() > 1 {
+            return Err(ApterError::MultipleRoots { roots });
+        }
+        Ok(())
+    }
+
+    /// Inserts a new item as a child of `parent`, rejecting out-of-range
+    /// parents instead of storing a dangling parent index. This is the same
+    /// as [`try_insert`](Self::try_insert).
+    ///
+    /// ```rust
+    /// use apter::{ApterTree, ApterError, NodeId};
+    /// let mut tree = ApterTree::new();
+    /// let root = tree.insert_root("root");
+    /// assert_eq!(tree.checked_insert("a", root), Ok(NodeId::new(1)));
+    /// assert_eq!(
+    ///     tree.checked_insert("b", NodeId::new(5)),
+    ///     Err(ApterError::InvalidParent { node: NodeId::new(2), parent: 5 }),
+    /// );
+    /// assert_eq!(tree.validate(), Ok(()));
+    /// ```
+    pub fn checked_insert(&mut self, v: T, parent: NodeId) -> Result<NodeId, ApterError> {
+        self.try_insert(v, parent)
+    }
+}